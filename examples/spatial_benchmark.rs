@@ -0,0 +1,120 @@
+//! Compares the spatial index against brute force for the cannonball-versus-tank query done by
+//! hit detection every tick.
+//!
+//! Run with `cargo run --release --example spatial_benchmark [tanks] [cannonballs]`.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use tanks_bevy::spatial::{SpatialEntry, SpatialGrid};
+
+const ARENA_SIZE: f32 = 200.0;
+const TANK_RADIUS: f32 = 1.6;
+const CANNONBALL_RADIUS: f32 = 0.2;
+const CELL_SIZE: f32 = 4.0;
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let tank_count = args.next().map_or(200, |arg| arg.parse().unwrap());
+    let cannonball_count = args.next().map_or(20_000, |arg| arg.parse().unwrap());
+
+    let mut random = XorShift(0x2545_f491_4f6c_dd1d);
+    let mut random_position = || {
+        Vec3::new(
+            (random.next_f32() - 0.5) * ARENA_SIZE,
+            random.next_f32() * 5.0,
+            (random.next_f32() - 0.5) * ARENA_SIZE,
+        )
+    };
+
+    let tanks: Vec<_> = (0..tank_count)
+        .map(|i| SpatialEntry {
+            entity: Entity::from_raw(i),
+            position: random_position(),
+            radius: TANK_RADIUS,
+        })
+        .collect();
+
+    let cannonballs: Vec<_> = (0..cannonball_count)
+        .map(|i| SpatialEntry {
+            entity: Entity::from_raw(tank_count + i),
+            position: random_position(),
+            radius: CANNONBALL_RADIUS,
+        })
+        .collect();
+
+    println!("{tank_count} tanks, {cannonball_count} cannonballs, {ITERATIONS} iterations");
+
+    let (brute_force_time, brute_force_hits) = measure(|| {
+        cannonballs
+            .iter()
+            .map(|cannonball| {
+                tanks
+                    .iter()
+                    .filter(|tank| overlaps(cannonball, tank))
+                    .count()
+            })
+            .sum()
+    });
+
+    // Rebuild both grids like the simulation does every tick, even though only the tank grid is
+    // queried here.
+    let mut tank_grid = SpatialGrid::new(CELL_SIZE);
+    let mut cannonball_grid = SpatialGrid::new(CELL_SIZE);
+
+    let (index_time, index_hits) = measure(|| {
+        tank_grid.clear();
+        cannonball_grid.clear();
+
+        for entry in &tanks {
+            tank_grid.insert(*entry);
+        }
+
+        for entry in &cannonballs {
+            cannonball_grid.insert(*entry);
+        }
+
+        cannonballs
+            .iter()
+            .map(|cannonball| {
+                tank_grid
+                    .query_radius(cannonball.position, cannonball.radius)
+                    .count()
+            })
+            .sum()
+    });
+
+    assert_eq!(brute_force_hits, index_hits);
+
+    println!("brute force:   {brute_force_time:>10.3?} per tick ({brute_force_hits} overlaps)");
+    println!("spatial index: {index_time:>10.3?} per tick, including the rebuild");
+}
+
+fn overlaps(a: &SpatialEntry, b: &SpatialEntry) -> bool {
+    let max_distance = a.radius + b.radius;
+    a.position.distance_squared(b.position) <= max_distance * max_distance
+}
+
+/// Runs `f` several times and returns the mean duration and the last result.
+fn measure(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut result = 0;
+
+    for _ in 0..ITERATIONS {
+        result = std::hint::black_box(f());
+    }
+
+    (start.elapsed() / ITERATIONS, result)
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
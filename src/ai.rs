@@ -0,0 +1,167 @@
+use bevy::{
+    ecs::{query::WorldQuery, system::SystemParam},
+    prelude::*,
+};
+use noise::Perlin;
+
+use crate::{
+    arena::ArenaBounds,
+    ballistics::ProjectilePhysics,
+    brain::{update_tank_roster, Brain, TankObservation, TankRoster},
+    combat::Health,
+    config::ScenarioConfig,
+    projectile::{launch_speed, CannonballSpawner},
+    simulation::SimulationSet,
+    tank::{AiTank, Suspension, TankMotion, TankVelocity},
+    terrain::Terrain,
+    turret::{TankParts, Turrets},
+    weapon::Weapon,
+};
+
+/// Drives the AI tanks with their [`Brain`]s.
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScenarioConfig>()
+            .init_resource::<Noise>()
+            .init_resource::<Terrain>()
+            .init_resource::<ArenaBounds>()
+            .init_resource::<TankRoster>()
+            .init_resource::<ProjectilePhysics>()
+            .add_systems(
+                FixedUpdate,
+                (update_tank_roster, ai_tank_update)
+                    .chain()
+                    .in_set(SimulationSet::Tanks),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct Noise {
+    pub generator: Perlin,
+}
+
+impl FromWorld for Noise {
+    fn from_world(world: &mut World) -> Self {
+        let seed = world
+            .get_resource::<ScenarioConfig>()
+            .map_or(Perlin::DEFAULT_SEED, |scenario| scenario.seed);
+
+        Self {
+            generator: Perlin::new(seed),
+        }
+    }
+}
+
+/// What the brains get to see besides their own tank.
+#[derive(SystemParam)]
+pub struct Surroundings<'w> {
+    time: Res<'w, Time>,
+    terrain: Res<'w, Terrain>,
+    bounds: Res<'w, ArenaBounds>,
+    roster: Res<'w, TankRoster>,
+    physics: Res<'w, ProjectilePhysics>,
+}
+
+/// The parts of an AI tank that its brain sees and drives.
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct AiTankQuery {
+    entity: Entity,
+    tank: &'static AiTank,
+    brain: &'static mut Brain,
+    transform: &'static mut Transform,
+    suspension: &'static mut Suspension,
+    motion: &'static mut TankMotion,
+    velocity: &'static mut TankVelocity,
+    health: &'static Health,
+    weapon: &'static mut Weapon,
+    parts: &'static TankParts,
+}
+
+pub fn ai_tank_update(
+    mut cannonballs: CannonballSpawner,
+    mut turrets: Turrets,
+    scenario: Res<ScenarioConfig>,
+    surroundings: Surroundings,
+    mut query: Query<AiTankQuery>,
+) {
+    let Surroundings {
+        time,
+        terrain,
+        bounds,
+        roster,
+        physics,
+    } = &surroundings;
+
+    for AiTankQueryItem {
+        entity,
+        tank,
+        mut brain,
+        mut transform,
+        mut suspension,
+        mut motion,
+        mut velocity,
+        health,
+        mut weapon,
+        parts,
+    } in &mut query
+    {
+        weapon.tick(time.delta_seconds());
+
+        let intent = brain.0.think(&TankObservation {
+            entity,
+            transform: &transform,
+            health,
+            weapon: &weapon,
+            delta: time.delta_seconds(),
+            elapsed: time.elapsed_seconds(),
+            launch_speed: launch_speed(scenario.muzzle_velocity),
+            inherited_velocity: cannonballs.inherited_velocity(velocity.val),
+            physics,
+            roster,
+            terrain,
+            bounds,
+        });
+
+        // Steer towards the brain's heading, turning back if it gets too close to the wall.
+
+        let start = transform.translation;
+        let target_heading = bounds.steer(transform.translation, intent.heading);
+        motion.steer_towards(target_heading, time.delta_seconds());
+        motion.throttle(intent.throttle, time.delta_seconds());
+        motion.drive(&mut transform.translation, time.delta_seconds());
+
+        suspension.ride(
+            &mut transform,
+            terrain,
+            motion.heading,
+            scenario.suspension_stiffness,
+            time.delta_seconds(),
+        );
+        velocity.measure(start, transform.translation, time.delta_seconds());
+
+        // Aim the turret wherever the brain wants to shoot, then fire along it.
+
+        if let Some(aim) = intent.aim {
+            turrets.aim(parts, &transform, aim);
+        }
+
+        if intent.fire && weapon.try_fire() {
+            let barrel = turrets.barrel_transform(parts, &transform);
+
+            match intent.aim {
+                Some(aim) => cannonballs.fire_with_velocity(
+                    entity,
+                    &barrel,
+                    aim,
+                    velocity.val,
+                    &tank.material,
+                ),
+                None => cannonballs.fire(entity, &barrel, velocity.val, &tank.material),
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ScenarioConfig,
+    simulation::SimulationSet,
+    tank::{wrap_angle, Suspension, TankMotion},
+    terrain::Terrain,
+};
+
+/// How far from the wall tanks start turning back with [`TankBoundary::Steer`]
+const STEER_MARGIN: f32 = 10.0;
+
+/// Keeps the tanks on the terrain, so the scene doesn't empty out as they wander off.
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ArenaBounds>()
+            .init_resource::<Terrain>()
+            .add_systems(FixedUpdate, confine_tanks.in_set(SimulationSet::Bounds));
+    }
+}
+
+/// What happens to a tank that drives into the arena wall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TankBoundary {
+    /// Stop at the wall
+    Clamp,
+    /// Bounce off the wall, mirroring the heading
+    Reflect,
+    /// Come back in on the opposite side
+    Wrap,
+    /// AI tanks turn back towards the center when close to the wall. Every tank stops at it.
+    Steer,
+}
+
+/// What happens to a cannonball that flies over the arena wall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectileBoundary {
+    Despawn,
+    /// Bounce off the wall, losing speed like a bounce off the ground
+    Bounce,
+}
+
+impl FromStr for TankBoundary {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "clamp" => Ok(Self::Clamp),
+            "reflect" => Ok(Self::Reflect),
+            "wrap" => Ok(Self::Wrap),
+            "steer" => Ok(Self::Steer),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for ProjectileBoundary {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "despawn" => Ok(Self::Despawn),
+            "bounce" => Ok(Self::Bounce),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The square the simulation takes place in, matching the terrain.
+#[derive(Resource, Clone)]
+pub struct ArenaBounds {
+    /// Distance from the center to each wall
+    pub half_size: f32,
+    pub tanks: TankBoundary,
+    pub projectiles: ProjectileBoundary,
+}
+
+impl FromWorld for ArenaBounds {
+    fn from_world(world: &mut World) -> Self {
+        let scenario = world.get_resource_or_insert_with(ScenarioConfig::default);
+
+        Self {
+            half_size: scenario.floor_size / 2.0,
+            tanks: scenario.tank_boundary,
+            projectiles: scenario.projectile_boundary,
+        }
+    }
+}
+
+impl ArenaBounds {
+    pub fn contains(&self, position: Vec3) -> bool {
+        position.x.abs() <= self.half_size && position.z.abs() <= self.half_size
+    }
+
+    /// Bends a heading towards the center as a tank nears the wall, fully turning it around at the
+    /// wall itself. The result is a steering target, so the tank still turns at its own rate. Only
+    /// has an effect with [`TankBoundary::Steer`].
+    pub fn steer(&self, position: Vec3, heading: f32) -> f32 {
+        if self.tanks != TankBoundary::Steer {
+            return heading;
+        }
+
+        let margin = STEER_MARGIN.min(self.half_size);
+        let depth = position.x.abs().max(position.z.abs()) - (self.half_size - margin);
+
+        if depth <= 0.0 {
+            return heading;
+        }
+
+        // Turn the shortest way round.
+        let to_center = (-position.x).atan2(-position.z);
+        let turn = wrap_angle(to_center - heading);
+
+        heading + turn * (depth / margin).min(1.0)
+    }
+
+    /// Moves a tank back inside the arena according to the tank policy, and returns its new
+    /// heading.
+    pub fn confine_tank(&self, translation: &mut Vec3, heading: f32) -> f32 {
+        let mut direction = Vec2::new(heading.sin(), heading.cos());
+        let half_size = self.half_size;
+
+        for (position, direction) in [
+            (&mut translation.x, &mut direction.x),
+            (&mut translation.z, &mut direction.y),
+        ] {
+            if position.abs() <= half_size {
+                continue;
+            }
+
+            match self.tanks {
+                TankBoundary::Clamp | TankBoundary::Steer => {
+                    *position = position.clamp(-half_size, half_size);
+                }
+                TankBoundary::Reflect => {
+                    *position = (2.0 * half_size - position.abs()) * position.signum();
+                    *direction = -direction.abs() * position.signum();
+                }
+                TankBoundary::Wrap => {
+                    *position = (*position + half_size).rem_euclid(2.0 * half_size) - half_size;
+                }
+            }
+        }
+
+        direction.x.atan2(direction.y)
+    }
+
+    /// Bounces a cannonball that has left the arena off the walls it crossed. It keeps
+    /// `restitution` of its speed into the walls and loses `friction` of its speed along them.
+    pub fn bounce(
+        &self,
+        translation: &mut Vec3,
+        velocity: &mut Vec3,
+        restitution: f32,
+        friction: f32,
+    ) {
+        let half_size = self.half_size;
+
+        for (position, velocity) in [
+            (&mut translation.x, &mut velocity.x),
+            (&mut translation.z, &mut velocity.z),
+        ] {
+            if position.abs() > half_size {
+                *position = position.clamp(-half_size, half_size);
+                *velocity = -velocity.abs() * position.signum() * restitution;
+            } else {
+                *velocity *= 1.0 - friction;
+            }
+        }
+
+        velocity.y *= 1.0 - friction;
+    }
+}
+
+fn confine_tanks(
+    bounds: Res<ArenaBounds>,
+    terrain: Res<Terrain>,
+    mut query: Query<(&mut Transform, &mut Suspension, &mut TankMotion)>,
+) {
+    for (mut transform, mut suspension, mut motion) in &mut query {
+        if bounds.contains(transform.translation) {
+            continue;
+        }
+
+        motion.heading = bounds.confine_tank(&mut transform.translation, motion.heading);
+
+        // Settle the tank at its new position without advancing the suspension.
+        suspension.ride(&mut transform, &terrain, motion.heading, 0.0, 0.0);
+    }
+}
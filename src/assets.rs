@@ -0,0 +1,108 @@
+use bevy::{app::AppExit, asset::LoadState, gltf::Gltf, prelude::*};
+
+use crate::state::GameState;
+
+/// The glTF files the game loads before spawning anything
+const TANK_FILE: &str = "tank.glb";
+const CANNONBALL_FILE: &str = "sphere.glb";
+const CUBE_FILE: &str = "cube.glb";
+
+/// The mesh taken from each file
+const MESH_LABEL: &str = "Mesh0/Primitive0";
+
+/// Loads the glTF assets at startup and watches them while [`GameState::Loading`]. A file that
+/// fails to load, or loads without the mesh the game needs, is reported by name and quits the app
+/// with an [`AssetLoadFailure`] rather than leaving an empty scene.
+pub struct GameAssetsPlugin;
+
+impl Plugin for GameAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameAssets>().add_systems(
+            Update,
+            report_load_failures.run_if(
+                in_state(GameState::Loading).and_then(not(resource_exists::<AssetLoadFailure>())),
+            ),
+        );
+    }
+}
+
+/// Handles to every asset the game needs. The meshes can only be used once [`assets_loaded`]
+/// holds.
+#[derive(Resource)]
+pub struct GameAssets {
+    pub tank_mesh: Handle<Mesh>,
+    pub cannonball_mesh: Handle<Mesh>,
+    /// Kept loaded, and checked for failures, along with the files the meshes come from
+    files: Vec<(&'static str, Handle<Gltf>)>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let mesh = |file| asset_server.load(format!("{file}#{MESH_LABEL}"));
+
+        Self {
+            tank_mesh: mesh(TANK_FILE),
+            cannonball_mesh: mesh(CANNONBALL_FILE),
+            files: [TANK_FILE, CANNONBALL_FILE, CUBE_FILE]
+                .into_iter()
+                .map(|file| (file, asset_server.load(file)))
+                .collect(),
+        }
+    }
+}
+
+impl GameAssets {
+    /// Whether every file has loaded, or the reason one of them never will.
+    pub fn check(&self, asset_server: &AssetServer, meshes: &Assets<Mesh>) -> Result<bool, String> {
+        for (file, handle) in &self.files {
+            match asset_server.get_load_state(handle) {
+                Some(LoadState::Loaded) => {}
+                Some(LoadState::Failed) => {
+                    return Err(format!("failed to load {file} from the assets folder"))
+                }
+                _ => return Ok(false),
+            }
+        }
+
+        // Labelled meshes are added along with their file, so a missing one is never coming.
+        for (file, mesh) in [
+            (TANK_FILE, &self.tank_mesh),
+            (CANNONBALL_FILE, &self.cannonball_mesh),
+        ] {
+            if !meshes.contains(mesh) {
+                return Err(format!("{file} has no {MESH_LABEL} mesh"));
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Why the assets never loaded. Inserted as the app is told to exit, so whoever runs it can tell
+/// the failure apart from a finished run.
+#[derive(Resource, Clone, Debug)]
+pub struct AssetLoadFailure(pub String);
+
+/// Run condition that holds once every asset has loaded.
+pub fn assets_loaded(
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    meshes: Res<Assets<Mesh>>,
+) -> bool {
+    assets.check(&asset_server, &meshes) == Ok(true)
+}
+
+fn report_load_failures(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    meshes: Res<Assets<Mesh>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    if let Err(message) = assets.check(&asset_server, &meshes) {
+        error!("{message}");
+        commands.insert_resource(AssetLoadFailure(message));
+        app_exit.send(AppExit);
+    }
+}
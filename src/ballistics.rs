@@ -0,0 +1,406 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use noise::{NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::Noise,
+    arena::{ArenaBounds, ProjectileBoundary},
+    config::ScenarioConfig,
+    terrain::Terrain,
+};
+
+/// Downwards acceleration of cannonballs under [`ProjectilePhysics::STANDARD`]
+pub const GRAVITY: f32 = 9.82;
+
+/// Cannonballs slower than the square root of this have come to rest
+const REST_SPEED_SQUARED: f32 = 0.1;
+
+/// Slices of the noise that the gusts of wind along X and Z follow, well away from the terrain
+/// and the AI tanks
+const WIND_NOISE_SLICES: [f64; 2] = [2000.5, 3000.5];
+
+/// The forces acting on cannonballs. Set for the whole scenario with `projectile_physics`, and
+/// can be changed at runtime to tune the ballistics.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default = "ProjectilePhysics::standard", deny_unknown_fields)]
+pub struct ProjectilePhysics {
+    /// Acceleration due to gravity
+    pub gravity: Vec3,
+    /// Quadratic air drag: cannonballs decelerate by `drag` times the square of their speed
+    /// relative to the wind. Zero flies in a vacuum, where the wind has no effect.
+    pub drag: f32,
+    /// Steady wind velocity
+    pub wind: Vec3,
+    /// Top speed of the gusts blowing across the ground on top of the steady wind. Zero turns
+    /// gusts off, saving a noise lookup per cannonball per tick.
+    pub gust_strength: f32,
+    /// Rough width of a gust
+    pub gust_size: f32,
+    /// Rough number of seconds a gust lasts
+    pub gust_period: f32,
+    /// Fraction of the speed into a surface that a cannonball bounces back with
+    pub restitution: f32,
+    /// Fraction of the speed along a surface that a cannonball loses when it bounces
+    pub friction: f32,
+}
+
+impl FromWorld for ProjectilePhysics {
+    fn from_world(world: &mut World) -> Self {
+        world
+            .get_resource::<ScenarioConfig>()
+            .map_or(Self::STANDARD, |scenario| {
+                scenario.projectile_physics.clone()
+            })
+    }
+}
+
+impl ProjectilePhysics {
+    /// Gravity alone, with the bounces the original benchmark used
+    pub const STANDARD: Self = Self {
+        gravity: Vec3::new(0.0, -GRAVITY, 0.0),
+        drag: 0.0,
+        wind: Vec3::ZERO,
+        gust_strength: 0.0,
+        gust_size: 50.0,
+        gust_period: 10.0,
+        restitution: 0.8,
+        friction: 0.2,
+    };
+
+    fn standard() -> Self {
+        Self::STANDARD
+    }
+
+    /// The wind at `position`, `elapsed` seconds into the simulation.
+    pub fn wind_at(&self, noise: &Perlin, position: Vec3, elapsed: f32) -> Vec3 {
+        if self.gust_strength == 0.0 {
+            return self.wind;
+        }
+
+        // Gusts drift through the noise over time, so they build and die down in place rather
+        // than sweeping across the arena.
+        let x = (position.x / self.gust_size) as f64;
+        let z = (position.z / self.gust_size) as f64;
+        let time = (elapsed / self.gust_period) as f64;
+        let [gust_x, gust_z] =
+            WIND_NOISE_SLICES.map(|slice| noise.get([x, slice + time, z]) as f32);
+
+        self.wind + Vec3::new(gust_x, 0.0, gust_z) * self.gust_strength
+    }
+
+    /// Gravity's pull towards the ground, which is all that [`solve_launch`] accounts for.
+    pub fn downward_gravity(&self) -> f32 {
+        -self.gravity.y
+    }
+}
+
+/// What a [`BallisticModel`] needs from the world, for systems that move cannonballs.
+#[derive(SystemParam)]
+pub struct Ballistics<'w> {
+    terrain: Res<'w, Terrain>,
+    bounds: Res<'w, ArenaBounds>,
+    physics: Res<'w, ProjectilePhysics>,
+    noise: Res<'w, Noise>,
+}
+
+impl Ballistics<'_> {
+    pub fn model(&self) -> BallisticModel<'_> {
+        BallisticModel {
+            terrain: &self.terrain,
+            bounds: &self.bounds,
+            physics: &self.physics,
+            noise: &self.noise.generator,
+        }
+    }
+}
+
+/// How cannonballs fly, bounce and come to rest. The simulation and trajectory predictions share
+/// it, so predictions match the real thing tick for tick.
+pub struct BallisticModel<'a> {
+    pub terrain: &'a Terrain,
+    pub bounds: &'a ArenaBounds,
+    pub physics: &'a ProjectilePhysics,
+    /// Drives the gusts of wind
+    pub noise: &'a Perlin,
+}
+
+impl BallisticModel<'_> {
+    /// Advances a cannonball by one tick, `elapsed` seconds into the simulation: moves it,
+    /// bounces it off the terrain and the arena walls and applies gravity, drag and wind. Returns
+    /// `false` once it has come to rest or left the arena, and should be removed.
+    pub fn step(
+        &self,
+        translation: &mut Vec3,
+        velocity: &mut Vec3,
+        elapsed: f32,
+        delta: f32,
+    ) -> bool {
+        let physics = self.physics;
+
+        // Move cannonball by the current velocity.
+
+        *translation += *velocity * delta;
+
+        // Bounce off the terrain if position drops below it, reflecting the velocity about the
+        // surface normal. Restitution scales the speed off the surface and friction slows it
+        // along the surface.
+
+        let ground = self.terrain.height_at(translation.x, translation.z) + 0.1;
+
+        if translation.y < ground {
+            translation.y = ground;
+
+            let normal = self.terrain.normal_at(translation.x, translation.z);
+            let into_ground = velocity.dot(normal);
+            let along_ground = *velocity - into_ground * normal;
+
+            *velocity = along_ground * (1.0 - physics.friction)
+                + normal * into_ground.abs() * physics.restitution;
+        }
+
+        // Leave the arena, or bounce off its walls.
+
+        if !self.bounds.contains(*translation) {
+            match self.bounds.projectiles {
+                ProjectileBoundary::Despawn => return false,
+                ProjectileBoundary::Bounce => {
+                    self.bounds
+                        .bounce(translation, velocity, physics.restitution, physics.friction)
+                }
+            }
+        }
+
+        if velocity.length_squared() < REST_SPEED_SQUARED {
+            return false;
+        }
+
+        // Acceleration due to gravity, and drag against the air, which carries the wind.
+
+        let mut acceleration = physics.gravity;
+
+        if physics.drag > 0.0 {
+            let airspeed = *velocity - physics.wind_at(self.noise, *translation, elapsed);
+            acceleration -= physics.drag * airspeed.length() * airspeed;
+        }
+
+        *velocity += acceleration * delta;
+        true
+    }
+
+    /// The positions a cannonball will pass through, one per tick, until it comes to rest, leaves
+    /// the arena or `max_ticks` have passed. Useful for drawing a trajectory preview.
+    pub fn trajectory(
+        &self,
+        mut translation: Vec3,
+        mut velocity: Vec3,
+        mut elapsed: f32,
+        delta: f32,
+        max_ticks: usize,
+    ) -> Vec<Vec3> {
+        let mut points = vec![translation];
+
+        for _ in 0..max_ticks {
+            let flying = self.step(&mut translation, &mut velocity, elapsed, delta);
+            points.push(translation);
+            elapsed += delta;
+
+            if !flying {
+                break;
+            }
+        }
+
+        points
+    }
+}
+
+/// Which of the two arcs through a target to fire along.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Arc {
+    /// Flatter and faster
+    #[default]
+    Low,
+    /// Lobbed over obstacles
+    High,
+}
+
+/// How to launch a cannonball to hit a target, from [`solve_launch`].
+#[derive(Clone, Copy, Debug)]
+pub struct FiringSolution {
+    /// Launch velocity, including the speed
+    pub velocity: Vec3,
+    /// Angle above the horizon, in radians
+    pub elevation: f32,
+    /// Angle about the Y axis, like a tank's heading
+    pub heading: f32,
+    /// Seconds until the cannonball reaches the target
+    pub flight_time: f32,
+    /// Where the target will be when the cannonball arrives
+    pub aim_point: Vec3,
+}
+
+/// Finds the launch velocity that hits a target moving at constant velocity, before the
+/// cannonball first bounces. Returns `None` if the target is out of range at this launch speed.
+///
+/// `gravity` is the downward acceleration, from [`ProjectilePhysics::downward_gravity`]. Drag,
+/// wind and any sideways gravity are left out, so the solution is only exact in a vacuum.
+///
+/// `delta` is the simulation tick length. The solution corrects for the simulation moving
+/// cannonballs before applying gravity each tick, so it is exact for the ticks rather than for
+/// continuous motion.
+pub fn solve_launch(
+    origin: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    speed: f32,
+    gravity: f32,
+    delta: f32,
+    arc: Arc,
+) -> Option<FiringSolution> {
+    let mut flight_time = 0.0;
+    let mut solution = None;
+
+    // Lead the target by the previous estimate of the flight time, which converges in a few
+    // iterations for targets slower than the cannonball.
+    for _ in 0..8 {
+        let aim_point = target + target_velocity * flight_time;
+        let offset = aim_point - origin;
+        let distance = offset.xz().length().max(f32::EPSILON);
+        let height = offset.y;
+
+        let speed_squared = speed * speed;
+        let discriminant = speed_squared * speed_squared
+            - gravity * (gravity * distance * distance + 2.0 * height * speed_squared);
+
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = match arc {
+            Arc::Low => speed_squared - discriminant.sqrt(),
+            Arc::High => speed_squared + discriminant.sqrt(),
+        };
+
+        let elevation = (root / (gravity * distance)).atan();
+        let heading = offset.x.atan2(offset.z);
+        flight_time = distance / (speed * elevation.cos());
+
+        let horizontal = Vec3::new(heading.sin(), 0.0, heading.cos()) * elevation.cos();
+        let velocity = (horizontal + Vec3::Y * elevation.sin()) * speed;
+
+        solution = Some(FiringSolution {
+            velocity,
+            elevation,
+            heading,
+            flight_time,
+            aim_point,
+        });
+    }
+
+    // Moving before accelerating each tick follows the same parabola as continuous motion
+    // launched half a tick's worth of gravity faster upwards.
+    solution.map(|solution| FiringSolution {
+        velocity: solution.velocity - Vec3::Y * gravity * delta / 2.0,
+        ..solution
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use noise::Perlin;
+
+    use super::*;
+    use crate::{arena::TankBoundary, projectile::CANNONBALL_SCALE};
+
+    const DELTA: f32 = 1.0 / 60.0;
+
+    /// The cannonball mesh is a sphere one unit across
+    const CANNONBALL_RADIUS: f32 = 0.5 * CANNONBALL_SCALE;
+
+    const ORIGIN: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+
+    fn solve(target: Vec3, target_velocity: Vec3, arc: Arc) -> Option<FiringSolution> {
+        solve_launch(
+            ORIGIN,
+            target,
+            target_velocity,
+            20.0,
+            ProjectilePhysics::STANDARD.downward_gravity(),
+            DELTA,
+            arc,
+        )
+    }
+
+    /// Fires a solution over flat ground in a vacuum and returns how close the cannonball passes
+    /// to the aim point.
+    fn miss_distance(solution: &FiringSolution) -> f32 {
+        let terrain = Terrain::generate(200.0, 1.0, 0.0, 40.0, |_, _| 0.0);
+        let bounds = ArenaBounds {
+            half_size: 100.0,
+            tanks: TankBoundary::Clamp,
+            projectiles: ProjectileBoundary::Despawn,
+        };
+        let noise = Perlin::new(0);
+        let model = BallisticModel {
+            terrain: &terrain,
+            bounds: &bounds,
+            physics: &ProjectilePhysics::STANDARD,
+            noise: &noise,
+        };
+
+        let ticks = (solution.flight_time / DELTA).ceil() as usize + 1;
+        let points = model.trajectory(ORIGIN, solution.velocity, 0.0, DELTA, ticks);
+
+        // The ticks are further apart than the cannonball is wide, so measure to the path
+        // between them.
+        points
+            .windows(2)
+            .map(|segment| distance_to_segment(solution.aim_point, segment[0], segment[1]))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    fn distance_to_segment(point: Vec3, start: Vec3, end: Vec3) -> f32 {
+        let along = end - start;
+        let t = ((point - start).dot(along) / along.length_squared()).clamp(0.0, 1.0);
+        point.distance(start + along * t)
+    }
+
+    #[test]
+    fn hits_a_stationary_target_on_both_arcs() {
+        let target = Vec3::new(30.0, 1.0, 12.0);
+
+        for arc in [Arc::Low, Arc::High] {
+            let solution = solve(target, Vec3::ZERO, arc).unwrap();
+
+            assert_eq!(solution.aim_point, target);
+            assert!(
+                miss_distance(&solution) <= CANNONBALL_RADIUS,
+                "{arc:?} arc missed"
+            );
+        }
+    }
+
+    #[test]
+    fn leads_a_moving_target_on_both_arcs() {
+        let target = Vec3::new(-20.0, 1.0, 15.0);
+        let target_velocity = Vec3::new(2.0, 0.0, -1.0);
+
+        for arc in [Arc::Low, Arc::High] {
+            let solution = solve(target, target_velocity, arc).unwrap();
+
+            assert_ne!(solution.aim_point, target);
+            assert!(
+                miss_distance(&solution) <= CANNONBALL_RADIUS,
+                "{arc:?} arc missed"
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_range_targets_have_no_solution() {
+        let target = Vec3::new(80.0, 1.0, 0.0);
+
+        for arc in [Arc::Low, Arc::High] {
+            assert!(solve(target, Vec3::ZERO, arc).is_none());
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use noise::{NoiseFn, Perlin};
+
+use crate::{
+    arena::ArenaBounds,
+    ballistics::{solve_launch, Arc, ProjectilePhysics},
+    combat::Health,
+    tank::{heading, AiTank, TankVelocity},
+    targeting::{max_range, TargetSelection, TARGET_HEIGHT},
+    team::Team,
+    terrain::Terrain,
+    turret::turret_position,
+    weapon::Weapon,
+};
+
+/// Decides what an AI tank does each tick. Give a tank a different [`Brain`] to change its
+/// behavior; the movement and firing systems carry out whatever it decides.
+pub trait TankBrain: Send + Sync + 'static {
+    fn think(&mut self, observation: &TankObservation) -> TankIntent;
+}
+
+/// What a tank wants to do this tick.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TankIntent {
+    /// Fraction of the tank's speed to drive at, negative to reverse, in the range -1..=1
+    pub throttle: f32,
+    /// The heading to steer towards, as an angle about the Y axis
+    pub heading: f32,
+    /// Fire if the weapon is ready
+    pub fire: bool,
+    /// Launch velocity to aim the turret along and fire with, relative to the tank, instead of
+    /// keeping the turret where it is
+    pub aim: Option<Vec3>,
+}
+
+/// Everything a [`TankBrain`] can see.
+pub struct TankObservation<'a> {
+    pub entity: Entity,
+    pub transform: &'a Transform,
+    pub health: &'a Health,
+    pub weapon: &'a Weapon,
+    /// Seconds since the last tick
+    pub delta: f32,
+    /// Seconds since the simulation started
+    pub elapsed: f32,
+    /// How fast this tank's cannonballs leave the cannon
+    pub launch_speed: f32,
+    /// Velocity this tank's cannonballs inherit on top of the launch velocity, zero when the
+    /// scenario turns inheritance off
+    pub inherited_velocity: Vec3,
+    /// The forces acting on cannonballs once fired
+    pub physics: &'a ProjectilePhysics,
+    /// Every tank, including this one
+    pub roster: &'a TankRoster,
+    pub terrain: &'a Terrain,
+    pub bounds: &'a ArenaBounds,
+}
+
+/// The [`TankBrain`] driving an AI tank.
+#[derive(Component)]
+pub struct Brain(pub Box<dyn TankBrain>);
+
+impl Brain {
+    pub fn new(brain: impl TankBrain) -> Self {
+        Self(Box::new(brain))
+    }
+}
+
+/// A tank as seen by the brains, captured at the start of each tick.
+#[derive(Clone, Copy, Debug)]
+pub struct TankState {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub heading: f32,
+    pub health: f32,
+    pub team: Option<Team>,
+}
+
+/// Snapshot of every tank, so brains can look at the others while their own tank is being moved.
+#[derive(Resource, Default)]
+pub struct TankRoster {
+    pub tanks: Vec<TankState>,
+}
+
+impl TankRoster {
+    pub fn get(&self, entity: Entity) -> Option<&TankState> {
+        self.tanks.iter().find(|tank| tank.entity == entity)
+    }
+
+    /// Every tank except `entity`.
+    pub fn others(&self, entity: Entity) -> impl Iterator<Item = &TankState> {
+        self.tanks.iter().filter(move |tank| tank.entity != entity)
+    }
+
+    /// Every tank that isn't `entity` or on its team.
+    pub fn enemies(&self, entity: Entity) -> impl Iterator<Item = &TankState> {
+        let team = self.get(entity).and_then(|tank| tank.team);
+
+        self.others(entity)
+            .filter(move |tank| !Team::allied(team, tank.team))
+    }
+}
+
+pub fn update_tank_roster(
+    mut roster: ResMut<TankRoster>,
+    query: Query<(Entity, &Transform, &TankVelocity, &Health, Option<&Team>)>,
+) {
+    roster.tanks.clear();
+
+    for (entity, transform, velocity, health, team) in &query {
+        roster.tanks.push(TankState {
+            entity,
+            position: transform.translation,
+            velocity: velocity.val,
+            heading: heading(transform.rotation),
+            health: health.current,
+            team: team.copied(),
+        });
+    }
+}
+
+/// Wanders along a perlin noise field at full speed, firing whenever the weapon is ready. Each
+/// tank samples its own slice of the noise, so they all take different paths.
+pub struct NoiseWander {
+    generator: Perlin,
+    /// Which slice of the noise to follow, usually [`AiTank::id`]
+    slice: f64,
+}
+
+impl NoiseWander {
+    pub fn new(generator: Perlin, tank: &AiTank) -> Self {
+        Self {
+            generator,
+            slice: tank.id as f64,
+        }
+    }
+}
+
+impl TankBrain for NoiseWander {
+    fn think(&mut self, observation: &TankObservation) -> TankIntent {
+        let seed = observation.transform.translation / 10.0;
+        let noise = self
+            .generator
+            .get([seed.x as f64, self.slice, seed.z as f64]) as f32;
+
+        TankIntent {
+            throttle: 1.0,
+            heading: (0.5 + noise) * 4.0 * PI,
+            fire: true,
+            aim: None,
+        }
+    }
+}
+
+/// Wanders like [`NoiseWander`], but only fires at an enemy it can hit, leading it to where it
+/// will be when the cannonball arrives.
+pub struct Marksman {
+    pub wander: NoiseWander,
+    pub selection: TargetSelection,
+    pub arc: Arc,
+}
+
+impl TankBrain for Marksman {
+    fn think(&mut self, observation: &TankObservation) -> TankIntent {
+        let mut intent = self.wander.think(observation);
+
+        // Aim from the turret pivot, since where the muzzle ends up depends on the aim.
+        let origin = turret_position(observation.transform);
+        let gravity = observation.physics.downward_gravity();
+        let solution = self
+            .selection
+            .select(
+                origin,
+                observation.roster.enemies(observation.entity),
+                max_range(observation.launch_speed, gravity),
+            )
+            .and_then(|target| {
+                // Solve relative to this tank, since its cannonballs carry its velocity.
+                solve_launch(
+                    origin,
+                    target.position + Vec3::Y * TARGET_HEIGHT,
+                    target.velocity - observation.inherited_velocity,
+                    observation.launch_speed,
+                    gravity,
+                    observation.delta,
+                    self.arc,
+                )
+            });
+
+        // Keep the turret on the target between shots, and only fire with a solution.
+        intent.fire = solution.is_some();
+        intent.aim = solution.map(|solution| solution.velocity);
+        intent
+    }
+}
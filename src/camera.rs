@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+
+use crate::{
+    state::GameState,
+    tank::{heading, PlayerTank},
+};
+
+/// Spawns the camera and keeps it above and behind the player tank once loading is done.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_camera).add_systems(
+            Update,
+            camera_update.run_if(not(in_state(GameState::Loading))),
+        );
+    }
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera3dBundle::default());
+}
+
+pub fn camera_update(
+    mut query_camera: Query<&mut Transform, With<Camera>>,
+    query_player_tank: Query<&Transform, (With<PlayerTank>, Without<Camera>)>,
+) {
+    // The camera stays where it is once the player tank has been destroyed.
+    let Ok(tank_transform) = query_player_tank.get_single() else {
+        return;
+    };
+
+    *query_camera.single_mut() = camera_transform(tank_transform);
+}
+
+pub fn camera_transform(tank_transform: &Transform) -> Transform {
+    // Position the camera above and behind the player tank. Only follow the tank's heading, so
+    // the camera doesn't rock with the hull on uneven ground.
+
+    let yaw = Quat::from_axis_angle(Vec3::Y, heading(tank_transform.rotation));
+    let camera_local_translation = yaw.mul_vec3(Vec3::new(0.0, 5.0, -10.0));
+
+    let translation = tank_transform.translation + camera_local_translation;
+    let target = tank_transform.translation + Vec3::Y;
+
+    Transform {
+        translation,
+        ..Default::default()
+    }
+    .looking_at(target, Vec3::Y)
+}
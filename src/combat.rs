@@ -0,0 +1,225 @@
+use std::sync::Mutex;
+
+use bevy::{prelude::*, render::primitives::Aabb};
+
+use crate::{
+    assets::GameAssets,
+    config::ScenarioConfig,
+    projectile::{Cannonball, Pooled, Velocity, CANNONBALL_SCALE},
+    simulation::SimulationSet,
+    spatial::SpatialIndex,
+    spawn::SpawnProtection,
+    tank::AiTank,
+    team::{FriendlyFire, Team},
+};
+
+/// Detects cannonballs hitting tanks, applies damage and destroys tanks that run out of health.
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScenarioConfig>()
+            .init_resource::<GameAssets>()
+            .add_event::<TankHit>()
+            .add_event::<TankDestroyed>()
+            .add_systems(
+                Update,
+                compute_hit_volumes.run_if(not(resource_exists::<HitVolumes>())),
+            )
+            .add_systems(
+                FixedUpdate,
+                (detect_hits, apply_damage)
+                    .chain()
+                    .in_set(SimulationSet::Hits)
+                    .run_if(resource_exists::<HitVolumes>()),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// Sent when a cannonball hits a tank other than the one that fired it, unless the scenario's
+/// [`FriendlyFire`] spares the shooter's teammates.
+#[derive(Event)]
+pub struct TankHit {
+    pub shooter: Entity,
+    pub victim: Entity,
+}
+
+/// Sent when a tank's health drops to zero, just before it is despawned.
+#[derive(Event)]
+pub struct TankDestroyed {
+    pub shooter: Entity,
+    pub victim: Entity,
+}
+
+/// Collision shapes derived from the tank and cannonball meshes once they have loaded.
+#[derive(Resource)]
+pub struct HitVolumes {
+    /// Bounding box of the tank in its local space
+    pub tank: Aabb,
+    pub cannonball_radius: f32,
+}
+
+impl HitVolumes {
+    /// Radius of a sphere around the tank origin that contains the whole tank.
+    pub fn tank_radius(&self) -> f32 {
+        Vec3::from(self.tank.center).length() + Vec3::from(self.tank.half_extents).length()
+    }
+
+    /// Tests a cannonball at `position` against a tank with the given transform.
+    pub fn hits_tank(&self, tank_transform: &Transform, position: Vec3) -> bool {
+        let local = tank_transform
+            .rotation
+            .inverse()
+            .mul_vec3(position - tank_transform.translation);
+
+        let center = Vec3::from(self.tank.center);
+        let half_extents = Vec3::from(self.tank.half_extents);
+        let closest = local.clamp(center - half_extents, center + half_extents);
+
+        local.distance_squared(closest) <= self.cannonball_radius * self.cannonball_radius
+    }
+}
+
+fn compute_hit_volumes(mut commands: Commands, assets: Res<GameAssets>, meshes: Res<Assets<Mesh>>) {
+    let tank_mesh = meshes.get(&assets.tank_mesh);
+    let cannonball_mesh = meshes.get(&assets.cannonball_mesh);
+
+    let (Some(tank_mesh), Some(cannonball_mesh)) = (tank_mesh, cannonball_mesh) else {
+        return;
+    };
+
+    let (Some(tank), Some(cannonball)) = (tank_mesh.compute_aabb(), cannonball_mesh.compute_aabb())
+    else {
+        return;
+    };
+
+    commands.insert_resource(HitVolumes {
+        tank,
+        cannonball_radius: cannonball.half_extents.max_element() * CANNONBALL_SCALE,
+    });
+}
+
+/// Tanks that cannonballs can hit.
+pub type HittableFilter = (With<Health>, Without<SpawnProtection>);
+
+/// Stops every cannonball that hits a tank, which makes the projectile system despawn it.
+/// Cannonballs fly through the shooter's teammates when friendly fire is off, and through tanks
+/// with spawn protection.
+///
+/// Hits are found in parallel, so they are sorted before being sent: which hit destroys a tank,
+/// and so who gets the kill, must not depend on thread scheduling.
+pub fn detect_hits(
+    scenario: Res<ScenarioConfig>,
+    hit_volumes: Res<HitVolumes>,
+    spatial_index: Res<SpatialIndex>,
+    mut hits: EventWriter<TankHit>,
+    mut cannonballs: Query<(&Transform, &mut Velocity, &Cannonball, Option<&Pooled>)>,
+    tanks: Query<(&Transform, Option<&Team>), HittableFilter>,
+    ai_tanks: Query<&AiTank>,
+) {
+    let friendly_fire = scenario.friendly_fire;
+
+    let found = Mutex::new(Vec::new());
+
+    cannonballs.par_iter_mut().for_each(
+        |(cannonball_transform, mut velocity, cannonball, pooled)| {
+            if !Pooled::is_active(pooled) {
+                return;
+            }
+
+            let position = cannonball_transform.translation;
+
+            let victim = spatial_index
+                .tanks
+                .query_radius(position, hit_volumes.cannonball_radius)
+                .filter(|entry| entry.entity != cannonball.owner)
+                .find_map(|entry| {
+                    let (tank_transform, team) = tanks.get(entry.entity).ok()?;
+                    let friendly = Team::allied(cannonball.team, team.copied());
+
+                    if friendly && friendly_fire == FriendlyFire::Off {
+                        return None;
+                    }
+
+                    hit_volumes
+                        .hits_tank(tank_transform, position)
+                        .then_some((entry.entity, friendly))
+                });
+
+            let Some((victim, friendly)) = victim else {
+                return;
+            };
+
+            velocity.val = Vec3::ZERO;
+
+            if friendly && friendly_fire == FriendlyFire::Blocked {
+                return;
+            }
+
+            // Entity ids are not stable between runs, so the order is based on content only.
+            let tank_id = |entity| ai_tanks.get(entity).ok().map(|tank| tank.id);
+            let order = (
+                tank_id(victim),
+                tank_id(cannonball.owner),
+                cannonball.spawned_at.to_bits(),
+                position.to_array().map(f32::to_bits),
+            );
+
+            found.lock().unwrap().push((
+                order,
+                TankHit {
+                    shooter: cannonball.owner,
+                    victim,
+                },
+            ));
+        },
+    );
+
+    let mut found = found.into_inner().unwrap();
+    found.sort_unstable_by_key(|(order, _)| *order);
+
+    hits.send_batch(found.into_iter().map(|(_, hit)| hit));
+}
+
+pub fn apply_damage(
+    mut commands: Commands,
+    scenario: Res<ScenarioConfig>,
+    mut hits: EventReader<TankHit>,
+    mut destroyed: EventWriter<TankDestroyed>,
+    mut tanks: Query<&mut Health>,
+) {
+    for hit in hits.read() {
+        let Ok(mut health) = tanks.get_mut(hit.victim) else {
+            continue;
+        };
+
+        // Already destroyed by an earlier hit this tick.
+        if health.current <= 0.0 {
+            continue;
+        }
+
+        health.current -= scenario.cannonball_damage;
+
+        if health.current <= 0.0 {
+            debug!("tank {:?} destroyed by {:?}", hit.victim, hit.shooter);
+
+            commands.entity(hit.victim).despawn_recursive();
+            destroyed.send(TankDestroyed {
+                shooter: hit.shooter,
+                victim: hit.victim,
+            });
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::{ProjectileBoundary, TankBoundary},
+    ballistics::ProjectilePhysics,
+    rules::MatchRules,
+    spawn::{load_spawn_map, SpawnLayout},
+    targeting::TargetSelection,
+    team::FriendlyFire,
+    weapon::WeaponStats,
+};
+
+const USAGE: &str = "\
+usage: tanks-bevy [options]
+
+    --headless                 run without a window or renderer
+    --frames <n>               exit after <n> frames (default 1000 when headless)
+    --report <path>            write <path>.json and <path>.csv benchmark reports on exit
+    --ticks <n>                deterministic mode: simulate exactly <n> fixed ticks, then exit
+                               and log a checksum of the final tank and cannonball positions
+    --config <file.ron>        load scenario parameters from a RON file
+
+scenario overrides, applied on top of --config:
+
+    --tanks <n>                number of tanks, including the player
+    --floor-size <size>        width and depth of the terrain
+    --tank-speed <speed>       top tank speed in units per second
+    --tank-acceleration <rate> speed tanks gain per second
+    --tank-braking <rate>      speed tanks lose per second when slowing down
+    --tank-turn-rate <rate>    how fast tanks turn, in radians per second
+    --muzzle-velocity <speed>  cannonball launch speed
+    --inherit-tank-velocity <on>
+                               add the firing tank's velocity to its cannonballs (true or false)
+    --gravity <x,y,z>          acceleration of cannonballs due to gravity
+    --drag <coefficient>       quadratic air drag on cannonballs, 0 for a vacuum
+    --wind <x,y,z>             steady wind velocity, which only acts through drag
+    --gust-strength <speed>    top speed of gusts on top of the steady wind, 0 for none
+    --gust-size <size>         rough width of a gust
+    --gust-period <seconds>    rough duration of a gust
+    --restitution <factor>     fraction of speed into a surface kept when a cannonball bounces
+    --friction <factor>        fraction of speed along a surface lost when a cannonball bounces
+    --bounce-damping <factor>  fraction of all velocity kept when a cannonball bounces, sets
+                               both restitution and friction
+    --shadow-map-size <size>   directional light shadow map resolution
+    --tick-rate <hz>           simulation ticks per second
+    --seed <seed>              seed for the noise driving the AI tanks
+    --tank-health <health>     health of every tank
+    --cannonball-damage <dmg>  health a tank loses when hit, 0 makes tanks indestructible
+    --spatial-cell-size <size> cell size of the spatial index used for hit detection
+    --pooled-cannonballs <on>  reuse cannonball entities instead of despawning them (true or
+                               false, toggle at runtime with P)
+    --weapon <preset>          standard, or stress to fire every tick without reloading
+    --fire-interval <seconds>  minimum time between shots
+    --magazine-size <n>        shots before reloading, 0 never reloads
+    --reload-time <seconds>    time to refill an empty magazine
+    --terrain-height <height>  height of the hills, 0 for a flat floor
+    --terrain-feature-size <size>
+                               rough width of the hills
+    --terrain-cell-size <size> spacing of the terrain mesh vertices
+    --suspension-stiffness <rate>
+                               how quickly tanks tilt to follow the slope, per second
+    --tank-boundary <policy>   what tanks do at the arena wall: clamp, reflect, wrap or steer
+    --projectile-boundary <policy>
+                               what cannonballs do at the arena wall: despawn or bounce
+    --ai-targeting <selection> how AI tanks pick targets to aim at: nearest, weakest, threat, or
+                               none to fire straight ahead whenever they can
+    --teams <n>                split the tanks into <n> teams, 0 for every tank for itself
+    --team-sizes <n,n,...>     tanks in each team, replacing --teams and --tanks
+    --friendly-fire <rule>     what cannonballs do to teammates: off to pass through them,
+                               blocked to stop without damage, or on
+    --match <mode>             play a match of free_for_all, team_deathmatch, king_of_the_hill
+                               or last_tank_standing, or none to run forever (the default)
+    --time-limit <seconds>     end the match after this long, 0 for no limit
+    --score-limit <score>      end the match when a side reaches this score, 0 for no limit
+    --hill-radius <radius>     radius of the hill in the middle for king_of_the_hill
+    --spawn-layout <layout>    where tanks start: ring, grid or random
+    --spawn-map <file.ron>     read the spawn points from a RON file instead
+    --spawn-spacing <distance> distance between spawn points
+    --respawn-delay <seconds>  bring destroyed tanks back after this long, or none (the default)
+    --spawn-protection <seconds>
+                               how long respawned tanks can't be hit
+
+    The match options other than --match start a free_for_all match if none is set. Headless
+    runs exit when the match ends.";
+
+#[derive(Resource, Default, Serialize)]
+pub struct RunSettings {
+    /// Run without a window or renderer, e.g. for benchmarking on CI machines
+    pub headless: bool,
+    /// Exit once this many frames have been run
+    pub frames: Option<u32>,
+    /// Write a benchmark report to this path with `.json` and `.csv` extensions on exit
+    pub report: Option<PathBuf>,
+    /// Simulate exactly this many fixed ticks, then exit
+    pub ticks: Option<u32>,
+}
+
+/// The parameters of the simulated scene.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScenarioConfig {
+    /// Number of tanks, including the player tank
+    pub tank_count: u32,
+    /// Width and depth of the square terrain
+    pub floor_size: f32,
+    /// Top tank speed in units per second
+    pub tank_speed: f32,
+    /// Speed tanks gain per second
+    pub tank_acceleration: f32,
+    /// Speed tanks lose per second when slowing down or reversing
+    pub tank_braking: f32,
+    /// How fast tanks turn, in radians per second
+    pub tank_turn_rate: f32,
+    /// Cannonball speed when leaving the cannon
+    pub muzzle_velocity: f32,
+    /// Add the firing tank's velocity to its cannonballs. Turn off to fire at the same speed
+    /// whether moving or not, as older benchmarks did.
+    pub inherit_tank_velocity: bool,
+    /// Gravity, drag, wind and bouncing of cannonballs
+    pub projectile_physics: ProjectilePhysics,
+    pub shadow_map_size: usize,
+    /// Simulation ticks per second
+    pub tick_rate: f64,
+    /// Seed for the noise function driving the AI tanks
+    pub seed: u32,
+    pub tank_health: f32,
+    /// Health a tank loses per cannonball hit
+    pub cannonball_damage: f32,
+    /// Cell size of the spatial index used for hit detection
+    pub spatial_cell_size: f32,
+    /// Start with the cannonball pool enabled
+    pub pooled_cannonballs: bool,
+    /// Fire rate and ammunition of every tank
+    pub weapon: WeaponStats,
+    /// Height of the hills, or zero for a flat floor
+    pub terrain_height: f32,
+    /// Rough width of the hills
+    pub terrain_feature_size: f32,
+    /// Spacing of the terrain mesh vertices
+    pub terrain_cell_size: f32,
+    /// How quickly tanks tilt to follow the slope under their tracks, per second
+    pub suspension_stiffness: f32,
+    /// What tanks do when they reach the edge of the terrain
+    pub tank_boundary: TankBoundary,
+    /// What cannonballs do when they fly past the edge of the terrain
+    pub projectile_boundary: ProjectileBoundary,
+    /// How AI tanks pick a target to aim at, or `None` to fire straight ahead whenever they can
+    pub ai_targeting: Option<TargetSelection>,
+    /// Number of teams to split the tanks into, or zero for every tank to fight for itself
+    pub team_count: u32,
+    /// Tanks in each team, in place of `team_count` and `tank_count` when not empty
+    pub team_sizes: Vec<u32>,
+    /// What cannonballs do to the shooter's teammates
+    pub friendly_fire: FriendlyFire,
+    /// How matches are won, or `None` to simulate forever
+    pub match_rules: Option<MatchRules>,
+    /// Where tanks start and respawn
+    pub spawn_layout: SpawnLayout,
+    /// Distance between spawn points, or the least distance for random ones
+    pub spawn_spacing: f32,
+    /// Seconds until a destroyed tank respawns, or `None` to leave it destroyed
+    pub respawn_delay: Option<f32>,
+    /// Seconds a respawned tank can't be hit for
+    pub spawn_protection: f32,
+}
+
+impl Default for ScenarioConfig {
+    fn default() -> Self {
+        Self {
+            tank_count: 20,
+            floor_size: 200.0,
+            tank_speed: 5.0,
+            tank_acceleration: 5.0,
+            tank_braking: 10.0,
+            tank_turn_rate: 2.0,
+            muzzle_velocity: 20.0,
+            inherit_tank_velocity: true,
+            projectile_physics: ProjectilePhysics::STANDARD,
+            shadow_map_size: 2048,
+            tick_rate: 60.0,
+            seed: 0,
+            tank_health: 100.0,
+            cannonball_damage: 10.0,
+            spatial_cell_size: 4.0,
+            pooled_cannonballs: false,
+            weapon: WeaponStats::STANDARD,
+            terrain_height: 3.0,
+            terrain_feature_size: 40.0,
+            terrain_cell_size: 1.0,
+            suspension_stiffness: 8.0,
+            tank_boundary: TankBoundary::Steer,
+            projectile_boundary: ProjectileBoundary::Despawn,
+            ai_targeting: Some(TargetSelection::Nearest),
+            team_count: 0,
+            team_sizes: Vec::new(),
+            friendly_fire: FriendlyFire::Off,
+            match_rules: None,
+            spawn_layout: SpawnLayout::Ring,
+            spawn_spacing: 4.0,
+            respawn_delay: None,
+            spawn_protection: 2.0,
+        }
+    }
+}
+
+/// Parses the command line into run settings and a scenario. The scenario starts from the
+/// defaults, then the `--config` file, then any individual overrides.
+pub fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(RunSettings, ScenarioConfig), String> {
+    let mut settings = RunSettings::default();
+    let mut config_path = None;
+    let mut overrides = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("{arg} expects a value\n\n{USAGE}"))
+        };
+
+        match arg.as_str() {
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            "--headless" => settings.headless = true,
+            "--frames" => settings.frames = Some(parse_value(&arg, &value()?)?),
+            "--report" => settings.report = Some(value()?.into()),
+            "--ticks" => settings.ticks = Some(parse_value(&arg, &value()?)?),
+            "--config" => config_path = Some(value()?),
+            _ if arg.starts_with("--") => {
+                let value = value()?;
+                overrides.push((arg, value));
+            }
+            _ => return Err(format!("unexpected argument: {arg}\n\n{USAGE}")),
+        }
+    }
+
+    // Headless runs have no window to close, so always give them an end.
+    if settings.headless && settings.frames.is_none() && settings.ticks.is_none() {
+        settings.frames = Some(1000);
+    }
+
+    let mut scenario = match config_path {
+        Some(path) => ScenarioConfig::load(&path)?,
+        None => ScenarioConfig::default(),
+    };
+
+    for (flag, value) in overrides {
+        scenario.apply_override(&flag, &value)?;
+    }
+
+    scenario.validate()?;
+
+    // Catch a broken spawn map now rather than once the app is running.
+    if let SpawnLayout::Map(path) = &scenario.spawn_layout {
+        load_spawn_map(path)?;
+    }
+
+    Ok((settings, scenario))
+}
+
+impl ScenarioConfig {
+    fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+        ron::from_str(&contents).map_err(|err| format!("failed to parse {path}: {err}"))
+    }
+
+    fn apply_override(&mut self, flag: &str, value: &str) -> Result<(), String> {
+        match flag {
+            "--tanks" => self.tank_count = parse_value(flag, value)?,
+            "--floor-size" => self.floor_size = parse_value(flag, value)?,
+            "--tank-speed" => self.tank_speed = parse_value(flag, value)?,
+            "--tank-acceleration" => self.tank_acceleration = parse_value(flag, value)?,
+            "--tank-braking" => self.tank_braking = parse_value(flag, value)?,
+            "--tank-turn-rate" => self.tank_turn_rate = parse_value(flag, value)?,
+            "--muzzle-velocity" => self.muzzle_velocity = parse_value(flag, value)?,
+            "--inherit-tank-velocity" => self.inherit_tank_velocity = parse_value(flag, value)?,
+            "--gravity" => self.projectile_physics.gravity = parse_vec3(flag, value)?,
+            "--drag" => self.projectile_physics.drag = parse_value(flag, value)?,
+            "--wind" => self.projectile_physics.wind = parse_vec3(flag, value)?,
+            "--gust-strength" => self.projectile_physics.gust_strength = parse_value(flag, value)?,
+            "--gust-size" => self.projectile_physics.gust_size = parse_value(flag, value)?,
+            "--gust-period" => self.projectile_physics.gust_period = parse_value(flag, value)?,
+            "--restitution" => self.projectile_physics.restitution = parse_value(flag, value)?,
+            "--friction" => self.projectile_physics.friction = parse_value(flag, value)?,
+            "--bounce-damping" => {
+                let damping: f32 = parse_value(flag, value)?;
+                self.projectile_physics.restitution = damping;
+                self.projectile_physics.friction = 1.0 - damping;
+            }
+            "--shadow-map-size" => self.shadow_map_size = parse_value(flag, value)?,
+            "--tick-rate" => self.tick_rate = parse_value(flag, value)?,
+            "--seed" => self.seed = parse_value(flag, value)?,
+            "--tank-health" => self.tank_health = parse_value(flag, value)?,
+            "--cannonball-damage" => self.cannonball_damage = parse_value(flag, value)?,
+            "--spatial-cell-size" => self.spatial_cell_size = parse_value(flag, value)?,
+            "--pooled-cannonballs" => self.pooled_cannonballs = parse_value(flag, value)?,
+            "--weapon" => {
+                self.weapon =
+                    WeaponStats::preset(value).ok_or(format!("unknown weapon preset: {value}"))?;
+            }
+            "--fire-interval" => self.weapon.fire_interval = parse_value(flag, value)?,
+            "--magazine-size" => self.weapon.magazine_size = parse_value(flag, value)?,
+            "--reload-time" => self.weapon.reload_time = parse_value(flag, value)?,
+            "--terrain-height" => self.terrain_height = parse_value(flag, value)?,
+            "--terrain-feature-size" => self.terrain_feature_size = parse_value(flag, value)?,
+            "--terrain-cell-size" => self.terrain_cell_size = parse_value(flag, value)?,
+            "--suspension-stiffness" => self.suspension_stiffness = parse_value(flag, value)?,
+            "--tank-boundary" => self.tank_boundary = parse_value(flag, value)?,
+            "--projectile-boundary" => self.projectile_boundary = parse_value(flag, value)?,
+            "--ai-targeting" => {
+                self.ai_targeting = match value {
+                    "none" => None,
+                    value => Some(parse_value(flag, value)?),
+                };
+            }
+            "--teams" => self.team_count = parse_value(flag, value)?,
+            "--team-sizes" => {
+                self.team_sizes = value
+                    .split(',')
+                    .map(|size| parse_value(flag, size.trim()))
+                    .collect::<Result<_, _>>()?;
+            }
+            "--friendly-fire" => self.friendly_fire = parse_value(flag, value)?,
+            "--match" => {
+                self.match_rules = match value {
+                    "none" => None,
+                    value => Some(MatchRules {
+                        mode: parse_value(flag, value)?,
+                        ..self.match_rules.take().unwrap_or_default()
+                    }),
+                };
+            }
+            "--time-limit" => self.match_rules().time_limit = parse_value(flag, value)?,
+            "--score-limit" => self.match_rules().score_limit = parse_value(flag, value)?,
+            "--hill-radius" => self.match_rules().hill_radius = parse_value(flag, value)?,
+            "--spawn-layout" => self.spawn_layout = parse_value(flag, value)?,
+            "--spawn-map" => self.spawn_layout = SpawnLayout::Map(value.into()),
+            "--spawn-spacing" => self.spawn_spacing = parse_value(flag, value)?,
+            "--respawn-delay" => {
+                self.respawn_delay = match value {
+                    "none" => None,
+                    value => Some(parse_value(flag, value)?),
+                };
+            }
+            "--spawn-protection" => self.spawn_protection = parse_value(flag, value)?,
+            _ => return Err(format!("unknown option: {flag}\n\n{USAGE}")),
+        }
+
+        Ok(())
+    }
+
+    /// Rejects values the simulation can't run with, whether they came from the file or the
+    /// command line.
+    fn validate(&self) -> Result<(), String> {
+        require_positive("--tick-rate", self.tick_rate)?;
+        require_positive("--spatial-cell-size", self.spatial_cell_size)?;
+        require_positive("--floor-size", self.floor_size)?;
+        require_positive("--terrain-cell-size", self.terrain_cell_size)?;
+        require_positive("--terrain-feature-size", self.terrain_feature_size)?;
+        require_positive("--gust-size", self.projectile_physics.gust_size)?;
+        require_positive("--gust-period", self.projectile_physics.gust_period)?;
+
+        Ok(())
+    }
+
+    /// The match rules to override, starting a free-for-all if there is no match yet.
+    fn match_rules(&mut self) -> &mut MatchRules {
+        self.match_rules.get_or_insert_with(MatchRules::default)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {flag}: {value}"))
+}
+
+fn require_positive(flag: &str, value: impl Into<f64>) -> Result<(), String> {
+    let value = value.into();
+
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "{flag} must be a positive number, got {value}\n\n{USAGE}"
+        ))
+    }
+}
+
+/// Parses a vector written as `x,y,z`.
+fn parse_vec3(flag: &str, value: &str) -> Result<Vec3, String> {
+    let components = value
+        .split(',')
+        .map(|component| parse_value(flag, component.trim()))
+        .collect::<Result<Vec<f32>, _>>()?;
+
+    match components[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(format!("invalid value for {flag}: {value}, expected x,y,z")),
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Instant;
+
+use bevy::{
+    app::AppExit,
+    core::FrameCount,
+    diagnostic::{
+        Diagnostic, DiagnosticId, Diagnostics, EntityCountDiagnosticsPlugin, LogDiagnosticsPlugin,
+        RegisterDiagnostic,
+    },
+    prelude::*,
+};
+
+use crate::{
+    config::RunSettings, report::ReportPlugin, rules::MatchEnded, simulation::SimulationTick,
+};
+
+/// Logs frame time and entity count, writes the benchmark report and ends the run after
+/// `--frames` or `--ticks`, or when the match ends in a headless run.
+pub struct TanksDiagnosticsPlugin;
+
+impl Plugin for TanksDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunSettings>()
+            .init_resource::<SimulationTick>()
+            .add_event::<MatchEnded>()
+            .register_diagnostic(Diagnostic::new(FRAME_TIME, "frame_time", 20).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(FPS, "fps", 20))
+            .add_plugins((
+                LogDiagnosticsPlugin::default(),
+                EntityCountDiagnosticsPlugin,
+                ReportPlugin,
+            ))
+            .add_systems(Update, measure_frame_time)
+            .add_systems(PostUpdate, exit_when_finished);
+    }
+}
+
+/// Wall-clock milliseconds per frame. Bevy's own frame time follows `Time<Real>`, which steps by
+/// exactly one tick per frame in benchmark runs rather than measuring anything.
+pub const FRAME_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x5c1b_2a64_93d7_4e0f_a3b8_61c2_0f9e_7d41);
+
+/// Wall-clock frames per second, see [`FRAME_TIME`].
+pub const FPS: DiagnosticId = DiagnosticId::from_u128(0x0e7a_4f19_c26b_48d3_9a05_d2e8_7b31_6c8f);
+
+fn measure_frame_time(mut diagnostics: Diagnostics, mut last_frame: Local<Option<Instant>>) {
+    let now = Instant::now();
+
+    let Some(previous) = last_frame.replace(now) else {
+        return;
+    };
+
+    let seconds = (now - previous).as_secs_f64();
+
+    if seconds > 0.0 {
+        diagnostics.add_measurement(FRAME_TIME, || seconds * 1000.0);
+        diagnostics.add_measurement(FPS, || 1.0 / seconds);
+    }
+}
+
+fn exit_when_finished(
+    run_settings: Res<RunSettings>,
+    frame_count: Res<FrameCount>,
+    tick: Res<SimulationTick>,
+    mut match_ended: EventReader<MatchEnded>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    // The frame count is only incremented at the end of the frame.
+    let frames_done = run_settings
+        .frames
+        .is_some_and(|frames| frame_count.0 + 1 >= frames);
+    let ticks_done = run_settings.ticks.is_some_and(|ticks| tick.0 >= ticks);
+    let match_done = match_ended.read().count() > 0 && run_settings.headless;
+
+    if frames_done || ticks_done || match_done {
+        app_exit.send(AppExit);
+    }
+}
@@ -0,0 +1,40 @@
+use bevy::{
+    app::PluginGroupBuilder, diagnostic::DiagnosticsPlugin, gltf::GltfPlugin, input::InputPlugin,
+    log::LogPlugin, prelude::*, render::mesh::skinning::SkinnedMeshInverseBindposes,
+    scene::ScenePlugin,
+};
+
+/// Everything the simulation needs, but no window, renderer or audio. Use this instead of
+/// `DefaultPlugins` to run on machines without a GPU. The glTF assets are still loaded so the
+/// scene is identical to the windowed run.
+pub struct HeadlessPlugins;
+
+impl PluginGroup for HeadlessPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        MinimalPlugins
+            .build()
+            .add(LogPlugin::default())
+            .add(TransformPlugin)
+            .add(HierarchyPlugin)
+            .add(DiagnosticsPlugin)
+            .add(InputPlugin)
+            .add(AssetPlugin::default())
+            .add(ScenePlugin)
+            .add(GltfPlugin::default())
+            .add(HeadlessAssetsPlugin)
+    }
+}
+
+/// Registers the asset types that the renderer would otherwise provide, so glTF files can be
+/// loaded and materials created.
+struct HeadlessAssetsPlugin;
+
+impl Plugin for HeadlessAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .init_asset::<Image>()
+            .init_asset::<AnimationClip>()
+            .init_asset::<SkinnedMeshInverseBindposes>();
+    }
+}
@@ -0,0 +1,103 @@
+//! A swarm of tanks driving around and firing cannonballs, packaged as Bevy plugins so the
+//! simulation can be embedded in other apps and tests.
+
+pub mod ai;
+pub mod arena;
+pub mod assets;
+pub mod ballistics;
+pub mod brain;
+pub mod camera;
+pub mod combat;
+pub mod config;
+pub mod diagnostics;
+pub mod headless;
+pub mod player;
+pub mod projectile;
+pub mod report;
+pub mod rules;
+pub mod setup;
+pub mod simulation;
+pub mod spatial;
+pub mod spawn;
+pub mod state;
+pub mod tank;
+pub mod targeting;
+pub mod team;
+pub mod terrain;
+pub mod turret;
+pub mod weapon;
+
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub use ai::AiPlugin;
+pub use arena::{ArenaBounds, ArenaPlugin};
+pub use assets::{AssetLoadFailure, GameAssets, GameAssetsPlugin};
+pub use ballistics::ProjectilePhysics;
+pub use brain::{Brain, TankBrain, TankIntent, TankObservation};
+pub use camera::CameraPlugin;
+pub use combat::{CombatPlugin, Health, TankDestroyed, TankHit};
+pub use diagnostics::TanksDiagnosticsPlugin;
+pub use player::PlayerPlugin;
+pub use projectile::{BallisticBody, Cannonball, ProjectilePlugin, Velocity};
+pub use rules::{GameMode, Match, MatchEnded, MatchMode, MatchPlugin, MatchRules, MatchStarted};
+pub use setup::SetupPlugin;
+pub use simulation::SimulationPlugin;
+pub use spatial::{SpatialIndex, SpatialIndexPlugin};
+pub use spawn::{SpawnPlugin, TankSpawner};
+pub use state::{GameState, GameStatePlugin};
+pub use tank::{AiTank, PlayerTank};
+pub use team::Team;
+pub use terrain::{Terrain, TerrainPlugin};
+pub use turret::{TankParts, TurretPlugin};
+pub use weapon::{Weapon, WeaponStats};
+
+/// The whole simulation. This is a plugin group, so individual parts can be swapped out:
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use tanks_bevy::{AiPlugin, TanksPlugin};
+/// App::new()
+///     .add_plugins((DefaultPlugins, TanksPlugin.build().disable::<AiPlugin>()))
+///     .run();
+/// ```
+///
+/// Insert the [`ScenarioConfig`](config::ScenarioConfig) before adding the plugins. The tick
+/// rate, shadow map and match, along with the terrain, arena bounds, noise and spawn points, are
+/// all taken from the scenario when the plugins are built, so one inserted afterwards reaches the
+/// systems but runs them on the default terrain, bounds and tick rate. The same goes for the
+/// [`RunSettings`](config::RunSettings), which decide whether time follows the wall clock:
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use tanks_bevy::{config::ScenarioConfig, TanksPlugin};
+/// App::new()
+///     .insert_resource(ScenarioConfig {
+///         tank_count: 50,
+///         ..default()
+///     })
+///     .add_plugins((DefaultPlugins, TanksPlugin))
+///     .run();
+/// ```
+pub struct TanksPlugin;
+
+impl PluginGroup for TanksPlugin {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(GameAssetsPlugin)
+            .add(TerrainPlugin)
+            .add(SetupPlugin)
+            .add(GameStatePlugin)
+            .add(SimulationPlugin)
+            .add(ArenaPlugin)
+            .add(AiPlugin)
+            .add(PlayerPlugin)
+            .add(TurretPlugin)
+            .add(ProjectilePlugin)
+            .add(SpatialIndexPlugin)
+            .add(CombatPlugin)
+            .add(SpawnPlugin)
+            .add(MatchPlugin)
+            .add(CameraPlugin)
+            .add(TanksDiagnosticsPlugin)
+    }
+}
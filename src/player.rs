@@ -0,0 +1,153 @@
+use bevy::{ecs::query::WorldQuery, prelude::*};
+
+use crate::{
+    config::ScenarioConfig,
+    projectile::CannonballSpawner,
+    simulation::SimulationSet,
+    tank::{PlayerTank, Suspension, TankMotion, TankVelocity},
+    terrain::Terrain,
+    turret::{TankParts, Turrets},
+    weapon::Weapon,
+};
+
+/// Drives the player tank with the keyboard or the first connected gamepad.
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerBindings>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<ScenarioConfig>()
+            .init_resource::<Terrain>()
+            .add_systems(Update, read_player_input)
+            .add_systems(FixedUpdate, player_tank_update.in_set(SimulationSet::Tanks));
+    }
+}
+
+/// Which keys and gamepad inputs control the player tank. Replace this resource to remap them.
+#[derive(Resource)]
+pub struct PlayerBindings {
+    pub forward: Vec<KeyCode>,
+    pub back: Vec<KeyCode>,
+    pub left: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub fire: Vec<KeyCode>,
+    pub throttle_axis: GamepadAxisType,
+    pub turn_axis: GamepadAxisType,
+    pub fire_button: GamepadButtonType,
+}
+
+impl Default for PlayerBindings {
+    fn default() -> Self {
+        Self {
+            forward: vec![KeyCode::W, KeyCode::Up],
+            back: vec![KeyCode::S, KeyCode::Down],
+            left: vec![KeyCode::A, KeyCode::Left],
+            right: vec![KeyCode::D, KeyCode::Right],
+            fire: vec![KeyCode::Space],
+            throttle_axis: GamepadAxisType::LeftStickY,
+            turn_axis: GamepadAxisType::LeftStickX,
+            fire_button: GamepadButtonType::RightTrigger2,
+        }
+    }
+}
+
+/// The player's intent, sampled every frame and applied on the next simulation tick.
+#[derive(Resource, Default)]
+pub struct PlayerInput {
+    /// Forward is positive, in the range -1..=1
+    pub throttle: f32,
+    /// Left is positive, in the range -1..=1
+    pub turn: f32,
+    /// Set while fire is held and cleared every tick. A press is latched until the next tick,
+    /// so short presses between ticks aren't lost.
+    pub fire: bool,
+}
+
+pub fn read_player_input(
+    bindings: Res<PlayerBindings>,
+    keyboard: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let key_axis = |positive: &[KeyCode], negative: &[KeyCode]| {
+        keyboard.any_pressed(positive.iter().copied()) as i8 as f32
+            - keyboard.any_pressed(negative.iter().copied()) as i8 as f32
+    };
+
+    let mut throttle = key_axis(&bindings.forward, &bindings.back);
+    let mut turn = key_axis(&bindings.left, &bindings.right);
+    let mut fire = keyboard.any_pressed(bindings.fire.iter().copied());
+
+    if let Some(gamepad) = gamepads.iter().next() {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+
+        throttle += axis(bindings.throttle_axis);
+        // Stick right is positive, but turning right is a negative rotation about Y.
+        turn -= axis(bindings.turn_axis);
+        fire |= buttons.pressed(GamepadButton::new(gamepad, bindings.fire_button));
+    }
+
+    input.throttle = throttle.clamp(-1.0, 1.0);
+    input.turn = turn.clamp(-1.0, 1.0);
+    input.fire |= fire;
+}
+
+/// The parts of the player tank that its update reads and drives.
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct PlayerTankQuery {
+    entity: Entity,
+    material: &'static Handle<StandardMaterial>,
+    parts: &'static TankParts,
+    transform: &'static mut Transform,
+    suspension: &'static mut Suspension,
+    motion: &'static mut TankMotion,
+    velocity: &'static mut TankVelocity,
+    weapon: &'static mut Weapon,
+}
+
+pub fn player_tank_update(
+    mut cannonballs: CannonballSpawner,
+    turrets: Turrets,
+    time: Res<Time>,
+    scenario: Res<ScenarioConfig>,
+    terrain: Res<Terrain>,
+    mut input: ResMut<PlayerInput>,
+    mut query: Query<PlayerTankQuery, With<PlayerTank>>,
+) {
+    let Ok(mut tank) = query.get_single_mut() else {
+        return;
+    };
+
+    // Turn and drive, then let the suspension settle the hull onto the terrain.
+
+    let transform = &mut *tank.transform;
+    let start = transform.translation;
+    tank.motion.turn(input.turn, time.delta_seconds());
+    tank.motion.throttle(input.throttle, time.delta_seconds());
+    tank.motion
+        .drive(&mut transform.translation, time.delta_seconds());
+
+    tank.suspension.ride(
+        transform,
+        &terrain,
+        tank.motion.heading,
+        scenario.suspension_stiffness,
+        time.delta_seconds(),
+    );
+    tank.velocity
+        .measure(start, transform.translation, time.delta_seconds());
+
+    tank.weapon.tick(time.delta_seconds());
+
+    if std::mem::take(&mut input.fire) && tank.weapon.try_fire() {
+        let barrel = turrets.barrel_transform(tank.parts, &tank.transform);
+        cannonballs.fire(tank.entity, &barrel, tank.velocity.val, tank.material);
+    }
+}
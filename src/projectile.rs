@@ -0,0 +1,359 @@
+use bevy::{
+    ecs::{query::WorldQuery, system::SystemParam},
+    prelude::*,
+};
+
+use crate::{
+    ai::Noise,
+    arena::ArenaBounds,
+    assets::GameAssets,
+    ballistics::{Ballistics, ProjectilePhysics},
+    config::ScenarioConfig,
+    simulation::SimulationSet,
+    team::Team,
+    terrain::Terrain,
+    turret::{muzzle_direction, muzzle_position, REST_BARREL_DIRECTION},
+};
+
+/// Toggles [`CannonballPool::enabled`] at runtime
+const TOGGLE_POOL_KEY: KeyCode = KeyCode::P;
+
+/// Moves cannonballs under the [`ProjectilePhysics`], bounces them off the terrain and despawns
+/// them once they come to rest or leave the arena. Other [`BallisticBody`] entities fly the same
+/// way.
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameAssets>()
+            .init_resource::<ScenarioConfig>()
+            .init_resource::<CannonballPool>()
+            .init_resource::<Terrain>()
+            .init_resource::<ArenaBounds>()
+            .init_resource::<Noise>()
+            .init_resource::<ProjectilePhysics>()
+            .add_systems(
+                Update,
+                (toggle_cannonball_pool, drain_cannonball_pool).chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (cannonball_update, ballistic_body_update).in_set(SimulationSet::Projectiles),
+            );
+    }
+}
+
+/// Cannonballs are the sphere mesh scaled down by this factor
+pub const CANNONBALL_SCALE: f32 = 0.2;
+
+#[derive(Component)]
+pub struct Velocity {
+    pub val: Vec3,
+}
+
+/// Marks a cannonball, as opposed to anything else with a [`Velocity`].
+#[derive(Component, Clone, Copy)]
+pub struct Cannonball {
+    /// The tank that fired it
+    pub owner: Entity,
+    /// The team of the tank that fired it, kept in case that tank is destroyed first
+    pub team: Option<Team>,
+    /// Simulation time it was fired at, in seconds
+    pub spawned_at: f32,
+}
+
+/// Marks something other than a cannonball that flies and bounces like one, e.g. debris. It stops
+/// when it comes to rest instead of being despawned.
+#[derive(Component)]
+pub struct BallisticBody;
+
+/// Marks a cannonball owned by the [`CannonballPool`]. Instead of being despawned when it comes
+/// to rest, it is hidden, deactivated and reused by the next shot.
+#[derive(Component)]
+pub struct Pooled {
+    pub active: bool,
+}
+
+impl Pooled {
+    /// Whether a cannonball should be simulated. Cannonballs outside the pool are always active.
+    pub fn is_active(pooled: Option<&Self>) -> bool {
+        pooled.is_none_or(|pooled| pooled.active)
+    }
+}
+
+/// Recycles cannonball entities instead of spawning and despawning one per shot, to compare the
+/// cost of entity churn against reuse.
+#[derive(Resource)]
+pub struct CannonballPool {
+    /// New cannonballs are taken from the pool and returned to it when they come to rest.
+    /// Disabling the pool despawns the free cannonballs; the rest are despawned as they land.
+    pub enabled: bool,
+    free: Vec<Entity>,
+}
+
+impl FromWorld for CannonballPool {
+    fn from_world(world: &mut World) -> Self {
+        let enabled = world
+            .get_resource::<ScenarioConfig>()
+            .is_some_and(|scenario| scenario.pooled_cannonballs);
+
+        Self {
+            enabled,
+            free: Vec::new(),
+        }
+    }
+}
+
+impl CannonballPool {
+    /// Number of inactive cannonballs waiting to be reused.
+    pub fn free_count(&self) -> usize {
+        self.free.len()
+    }
+}
+
+/// Fires cannonballs from a system, through the pool if it is enabled.
+#[derive(SystemParam)]
+pub struct CannonballSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    assets: Res<'w, GameAssets>,
+    pool: ResMut<'w, CannonballPool>,
+    scenario: Res<'w, ScenarioConfig>,
+    time: Res<'w, Time>,
+    teams: Query<'w, 's, &'static Team>,
+}
+
+impl CannonballSpawner<'_, '_> {
+    /// Fires along the barrel at the scenario's launch speed.
+    pub fn fire(
+        &mut self,
+        shooter: Entity,
+        barrel_transform: &GlobalTransform,
+        tank_velocity: Vec3,
+        material: &Handle<StandardMaterial>,
+    ) {
+        let speed = launch_speed(self.scenario.muzzle_velocity);
+        let velocity = muzzle_direction(barrel_transform) * speed;
+        self.fire_with_velocity(shooter, barrel_transform, velocity, tank_velocity, material);
+    }
+
+    /// Fires from the tip of the barrel with any launch velocity relative to the tank, e.g. from
+    /// a [`FiringSolution`](crate::ballistics::FiringSolution).
+    pub fn fire_with_velocity(
+        &mut self,
+        shooter: Entity,
+        barrel_transform: &GlobalTransform,
+        velocity: Vec3,
+        tank_velocity: Vec3,
+        material: &Handle<StandardMaterial>,
+    ) {
+        let velocity = velocity + self.inherited_velocity(tank_velocity);
+        let cannonball = Cannonball {
+            owner: shooter,
+            team: self.teams.get(shooter).ok().copied(),
+            spawned_at: self.time.elapsed_seconds(),
+        };
+
+        spawn_cannonball(
+            &mut self.commands,
+            &mut self.pool,
+            cannonball,
+            barrel_transform,
+            velocity,
+            self.assets.cannonball_mesh.clone_weak(),
+            material.clone_weak(),
+        );
+    }
+
+    /// The part of a tank's velocity its cannonballs carry, which is all of it unless the
+    /// scenario turns inheritance off.
+    pub fn inherited_velocity(&self, tank_velocity: Vec3) -> Vec3 {
+        if self.scenario.inherit_tank_velocity {
+            tank_velocity
+        } else {
+            Vec3::ZERO
+        }
+    }
+}
+
+/// How fast cannonballs leave the cannon. [`REST_BARREL_DIRECTION`] isn't normalized, so this is
+/// a bit faster than `muzzle_velocity` itself.
+pub fn launch_speed(muzzle_velocity: f32) -> f32 {
+    REST_BARREL_DIRECTION.length() * muzzle_velocity
+}
+
+pub fn spawn_cannonball(
+    commands: &mut Commands,
+    pool: &mut CannonballPool,
+    cannonball: Cannonball,
+    barrel_transform: &GlobalTransform,
+    velocity: Vec3,
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+) {
+    // Shoot from the tip of the barrel, wherever the turret is aimed and however the hull is
+    // tilted.
+    let transform = Transform {
+        translation: muzzle_position(barrel_transform),
+        rotation: barrel_transform.compute_transform().rotation,
+        scale: Vec3::splat(CANNONBALL_SCALE),
+    };
+
+    let velocity = Velocity { val: velocity };
+
+    if !pool.enabled {
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                material,
+                transform,
+                ..default()
+            },
+            velocity,
+            cannonball,
+        ));
+        return;
+    }
+
+    // Reusing an entity only overwrites components it already has, so it never changes archetype.
+    let pooled = Pooled { active: true };
+
+    match pool.free.pop() {
+        Some(entity) => {
+            commands.entity(entity).insert((
+                material,
+                transform,
+                Visibility::Inherited,
+                velocity,
+                cannonball,
+                pooled,
+            ));
+        }
+        None => {
+            commands.spawn((
+                PbrBundle {
+                    mesh,
+                    material,
+                    transform,
+                    ..default()
+                },
+                velocity,
+                cannonball,
+                pooled,
+            ));
+        }
+    }
+}
+
+/// The parts of a cannonball that the projectile system moves and recycles.
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct CannonballQuery {
+    entity: Entity,
+    cannonball: &'static Cannonball,
+    transform: &'static mut Transform,
+    velocity: &'static mut Velocity,
+    visibility: &'static mut Visibility,
+    pooled: Option<&'static mut Pooled>,
+}
+
+pub fn cannonball_update(
+    par_commands: ParallelCommands,
+    time: Res<Time>,
+    pool: Res<CannonballPool>,
+    ballistics: Ballistics,
+    mut query: Query<CannonballQuery>,
+) {
+    let model = ballistics.model();
+
+    query.par_iter_mut().for_each(|mut cannonball| {
+        if !Pooled::is_active(cannonball.pooled.as_deref()) {
+            return;
+        }
+
+        let translation = &mut cannonball.transform.translation;
+
+        if model.step(
+            translation,
+            &mut cannonball.velocity.val,
+            time.elapsed_seconds(),
+            time.delta_seconds(),
+        ) {
+            return;
+        }
+
+        // Despawn once it comes to rest or leaves the arena, or return it to the pool if it came
+        // from there and the pool is still enabled.
+
+        let entity = cannonball.entity;
+
+        match cannonball.pooled {
+            Some(mut pooled) if pool.enabled => {
+                pooled.active = false;
+                *cannonball.visibility = Visibility::Hidden;
+
+                par_commands.command_scope(|mut commands| {
+                    commands.add(move |world: &mut World| {
+                        world.resource_mut::<CannonballPool>().free.push(entity);
+                    });
+                });
+            }
+            _ => {
+                par_commands.command_scope(|mut commands| {
+                    commands.entity(entity).despawn();
+                });
+            }
+        }
+    });
+}
+
+/// Moves every [`BallisticBody`] like a cannonball, stopping it once it comes to rest and
+/// despawning it if it leaves the arena.
+pub fn ballistic_body_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    ballistics: Ballistics,
+    bounds: Res<ArenaBounds>,
+    mut query: Query<(Entity, &mut Transform, &mut Velocity), With<BallisticBody>>,
+) {
+    let model = ballistics.model();
+
+    for (entity, mut transform, mut velocity) in &mut query {
+        if velocity.val == Vec3::ZERO {
+            continue;
+        }
+
+        if model.step(
+            &mut transform.translation,
+            &mut velocity.val,
+            time.elapsed_seconds(),
+            time.delta_seconds(),
+        ) {
+            continue;
+        }
+
+        if bounds.contains(transform.translation) {
+            velocity.val = Vec3::ZERO;
+        } else {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn toggle_cannonball_pool(keyboard: Res<Input<KeyCode>>, mut pool: ResMut<CannonballPool>) {
+    if keyboard.just_pressed(TOGGLE_POOL_KEY) {
+        pool.enabled = !pool.enabled;
+        info!("cannonball pool enabled: {}", pool.enabled);
+    }
+}
+
+/// Despawns the free cannonballs once the pool has been disabled. Runs every frame, since every
+/// shot changes the pool and the check is cheaper than tracking who disabled it.
+fn drain_cannonball_pool(mut commands: Commands, mut pool: ResMut<CannonballPool>) {
+    if pool.enabled {
+        return;
+    }
+
+    for entity in pool.free.drain(..) {
+        commands.entity(entity).despawn();
+    }
+}
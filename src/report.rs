@@ -0,0 +1,243 @@
+use std::{fs, io, path::Path};
+
+use bevy::{
+    app::AppExit,
+    diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin},
+    prelude::*,
+};
+use serde::Serialize;
+
+use crate::{
+    config::{RunSettings, ScenarioConfig},
+    diagnostics::FRAME_TIME,
+    projectile::Pooled,
+    rules::MatchRules,
+    simulation::{state_checksum, SimulatedFilter, SimulationTick},
+    state::GameState,
+};
+
+/// Records frame time and entity count every frame of [`GameState::Playing`], so loading, menus
+/// and pauses don't skew the stats, and writes a benchmark report when the app exits, if
+/// `--report <path>` was given. Apps that exit before the assets have loaded write no report,
+/// since nothing was simulated.
+pub struct ReportPlugin;
+
+impl Plugin for ReportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameSamples>().add_systems(
+            Last,
+            (
+                record_samples.run_if(state_exists_and_equals(GameState::Playing)),
+                write_report.run_if(
+                    on_event::<AppExit>()
+                        .and_then(not(state_exists_and_equals(GameState::Loading))),
+                ),
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct FrameSamples {
+    /// Frame times in milliseconds
+    frame_times: Vec<f64>,
+    entity_counts: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    parameters: &'a RunSettings,
+    scenario: &'a ScenarioConfig,
+    frames_recorded: usize,
+    ticks_simulated: u32,
+    /// Only reported for `--ticks` runs, where it is reproducible
+    state_checksum: Option<String>,
+    frame_time_ms: FrameTimeSummary,
+    peak_entity_count: u64,
+}
+
+#[derive(Serialize)]
+struct FrameTimeSummary {
+    min: f64,
+    mean: f64,
+    median: f64,
+    p95: f64,
+    p99: f64,
+}
+
+impl FrameTimeSummary {
+    fn from_samples(samples: &[f64]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        // Nearest-rank percentile.
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted.get(rank.max(1) - 1).copied().unwrap_or(0.0)
+        };
+
+        Self {
+            min: sorted.first().copied().unwrap_or(0.0),
+            mean: sorted.iter().sum::<f64>() / sorted.len().max(1) as f64,
+            median: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        }
+    }
+}
+
+fn record_samples(diagnostics: Res<DiagnosticsStore>, mut samples: ResMut<FrameSamples>) {
+    let latest = |id| {
+        diagnostics
+            .get(id)
+            .and_then(|diagnostic| diagnostic.value())
+    };
+
+    if let Some(frame_time) = latest(FRAME_TIME) {
+        samples.frame_times.push(frame_time);
+    }
+
+    if let Some(entity_count) = latest(EntityCountDiagnosticsPlugin::ENTITY_COUNT) {
+        samples.entity_counts.push(entity_count);
+    }
+}
+
+fn write_report(
+    run_settings: Res<RunSettings>,
+    scenario: Res<ScenarioConfig>,
+    samples: Res<FrameSamples>,
+    tick: Res<SimulationTick>,
+    query: Query<(&Transform, Option<&Pooled>), SimulatedFilter>,
+) {
+    let Some(path) = &run_settings.report else {
+        return;
+    };
+
+    let report = Report {
+        parameters: &run_settings,
+        scenario: &scenario,
+        frames_recorded: samples.frame_times.len(),
+        ticks_simulated: tick.0,
+        state_checksum: run_settings
+            .ticks
+            .map(|_| format!("{:016x}", state_checksum(&query))),
+        frame_time_ms: FrameTimeSummary::from_samples(&samples.frame_times),
+        peak_entity_count: samples.entity_counts.iter().copied().fold(0.0, f64::max) as u64,
+    };
+
+    let json_path = path.with_extension("json");
+    let csv_path = path.with_extension("csv");
+
+    match write_json(&json_path, &report).and_then(|()| write_csv(&csv_path, &report)) {
+        Ok(()) => info!(
+            "wrote benchmark report to {} and {}",
+            json_path.display(),
+            csv_path.display()
+        ),
+        Err(err) => error!("failed to write benchmark report: {err}"),
+    }
+}
+
+fn write_json(path: &Path, report: &Report) -> io::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(report)?)
+}
+
+/// Writes a header and a single row, so reports from several runs are easy to concatenate. Every
+/// scenario has the same columns: those for settings it leaves out, like the match rules of a run
+/// without a match, are empty.
+fn write_csv(path: &Path, report: &Report) -> io::Result<()> {
+    let template_scenario = ScenarioConfig {
+        match_rules: Some(MatchRules::default()),
+        ..default()
+    };
+    let templates = [
+        serde_json::to_value(RunSettings::default())?,
+        serde_json::to_value(template_scenario)?,
+    ];
+    let parameters = [
+        serde_json::to_value(report.parameters)?,
+        serde_json::to_value(report.scenario)?,
+    ];
+
+    let mut header = Vec::new();
+    let mut row = Vec::new();
+
+    for (template, parameters) in templates.iter().zip(&parameters) {
+        flatten_csv_columns("", template, Some(parameters), &mut header, &mut row);
+    }
+
+    header.push("state_checksum".to_string());
+    row.push(report.state_checksum.clone().unwrap_or_default());
+
+    let summary = &report.frame_time_ms;
+    let stats = [
+        ("frames_recorded", report.frames_recorded as f64),
+        ("ticks_simulated", report.ticks_simulated as f64),
+        ("frame_time_min_ms", summary.min),
+        ("frame_time_mean_ms", summary.mean),
+        ("frame_time_median_ms", summary.median),
+        ("frame_time_p95_ms", summary.p95),
+        ("frame_time_p99_ms", summary.p99),
+        ("peak_entity_count", report.peak_entity_count as f64),
+    ];
+
+    for (key, value) in stats {
+        header.push(key.to_string());
+        row.push(value.to_string());
+    }
+
+    let line = |fields: Vec<String>| {
+        fields
+            .iter()
+            .map(|field| escape_csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    fs::write(path, format!("{}\n{}\n", line(header), line(row)))
+}
+
+/// Turns the nested objects of `template` into `parent.child` columns, filled in from `value`.
+/// Columns missing from `value`, like the fields of an option that isn't set, are left empty.
+/// Anything else that doesn't fit in one column, like a vector or a spawn map, is written as JSON.
+fn flatten_csv_columns(
+    prefix: &str,
+    template: &serde_json::Value,
+    value: Option<&serde_json::Value>,
+    header: &mut Vec<String>,
+    row: &mut Vec<String>,
+) {
+    match template {
+        serde_json::Value::Object(fields) => {
+            for (key, template) in fields {
+                let value = value.and_then(|value| value.get(key));
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+
+                flatten_csv_columns(&key, template, value, header, row);
+            }
+        }
+        _ => {
+            header.push(prefix.to_string());
+            row.push(match value {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            });
+        }
+    }
+}
+
+/// Quotes a field as RFC 4180 requires if it contains a comma, quote or line break, doubling any
+/// quotes inside it.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
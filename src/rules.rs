@@ -0,0 +1,555 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use bevy::{ecs::query::WorldQuery, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combat::{Health, TankDestroyed},
+    config::ScenarioConfig,
+    simulation::SimulationSet,
+    tank::AiTank,
+    team::Team,
+};
+
+/// Scores the match set by the scenario's `match_rules` and ends it once it has been won, so AI
+/// tanks can be pitted against each other. Without `match_rules` the simulation runs forever.
+pub struct MatchPlugin;
+
+impl Plugin for MatchPlugin {
+    fn build(&self, app: &mut App) {
+        let scenario = app
+            .world
+            .get_resource_or_insert_with(ScenarioConfig::default);
+
+        if let Some(rules) = scenario.match_rules.clone() {
+            let respawns = scenario.respawn_delay.is_some();
+
+            app.insert_resource(Match {
+                respawns,
+                ..Match::new(rules)
+            });
+        }
+
+        app.add_event::<TankDestroyed>()
+            .add_event::<MatchStarted>()
+            .add_event::<MatchEnded>()
+            .add_systems(
+                FixedUpdate,
+                run_match
+                    .in_set(SimulationSet::Rules)
+                    .run_if(resource_exists::<Match>()),
+            );
+    }
+}
+
+/// How a match is played, set with `match_rules` in the scenario.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatchRules {
+    pub mode: GameMode,
+    /// Seconds until the match ends, or zero to play until it is won
+    pub time_limit: f32,
+    /// Score that wins the match, or zero for no limit. Last tank standing has no score limit.
+    pub score_limit: f32,
+    /// Radius of the hill in the middle of the arena, for king of the hill
+    pub hill_radius: f32,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::FreeForAll,
+            time_limit: 0.0,
+            score_limit: 0.0,
+            hill_radius: 15.0,
+        }
+    }
+}
+
+/// The built-in [`MatchMode`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    /// Every tank for itself, scoring a point per kill
+    FreeForAll,
+    /// Teams score a point per kill and lose one per teamkill
+    TeamDeathmatch,
+    /// Sides score a point per second while they are alone on the hill
+    KingOfTheHill,
+    /// The last side with tanks left wins
+    LastTankStanding,
+}
+
+impl FromStr for GameMode {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "free_for_all" => Ok(Self::FreeForAll),
+            "team_deathmatch" => Ok(Self::TeamDeathmatch),
+            "king_of_the_hill" => Ok(Self::KingOfTheHill),
+            "last_tank_standing" => Ok(Self::LastTankStanding),
+            _ => Err(()),
+        }
+    }
+}
+
+impl GameMode {
+    pub fn build(self, rules: &MatchRules) -> Box<dyn MatchMode> {
+        match self {
+            Self::FreeForAll => Box::new(FreeForAll),
+            Self::TeamDeathmatch => Box::new(TeamDeathmatch),
+            Self::KingOfTheHill => Box::new(KingOfTheHill {
+                center: Vec2::ZERO,
+                radius: rules.hill_radius,
+            }),
+            Self::LastTankStanding => Box::new(LastTankStanding),
+        }
+    }
+}
+
+/// Who scores in a match: a whole team, or a tank on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Team(Team),
+    /// A tank by its id, which it keeps when it respawns
+    Tank(u32),
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Team(team) => write!(f, "team {}", team.0),
+            Self::Tank(id) => write!(f, "tank {id}"),
+        }
+    }
+}
+
+/// How one side is doing.
+#[derive(Clone, Copy, Debug)]
+pub struct Standing {
+    pub side: Side,
+    pub score: f32,
+    pub kills: u32,
+    pub deaths: u32,
+    /// Tanks the side has left
+    pub alive: u32,
+    /// Seconds into the match that the side lost its last tank, until one respawns
+    pub eliminated_at: Option<f32>,
+}
+
+impl Standing {
+    fn new(side: Side) -> Self {
+        Self {
+            side,
+            score: 0.0,
+            kills: 0,
+            deaths: 0,
+            alive: 0,
+            eliminated_at: None,
+        }
+    }
+}
+
+/// A living tank as the match rules see it.
+#[derive(Clone, Copy, Debug)]
+pub struct Contender {
+    pub side: Side,
+    pub position: Vec3,
+}
+
+/// Decides who scores what in a match. Implement this for rules beyond the [`GameMode`]s and
+/// insert a [`Match`] with it.
+pub trait MatchMode: Send + Sync + 'static {
+    /// Which side a tank plays for: its team, or itself if it has none.
+    fn side(&self, id: u32, team: Option<Team>) -> Side {
+        team.map_or(Side::Tank(id), Side::Team)
+    }
+
+    /// Points the shooter's side scores for a kill.
+    fn kill_points(&self, teamkill: bool) -> f32 {
+        if teamkill {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Awards points over time, e.g. for holding ground. Called every tick with the living tanks.
+    fn award(&mut self, _contenders: &[Contender], _delta: f32, _standings: &mut [Standing]) {}
+
+    /// Whether reaching the score limit ends the match.
+    fn has_score_limit(&self) -> bool {
+        true
+    }
+
+    /// Orders the final standings, winner first.
+    fn rank(&self, a: &Standing, b: &Standing) -> Ordering {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.kills.cmp(&a.kills))
+            .then(a.deaths.cmp(&b.deaths))
+    }
+}
+
+pub struct FreeForAll;
+
+impl MatchMode for FreeForAll {
+    /// Teams are ignored, so every kill counts.
+    fn side(&self, id: u32, _team: Option<Team>) -> Side {
+        Side::Tank(id)
+    }
+}
+
+pub struct TeamDeathmatch;
+
+impl MatchMode for TeamDeathmatch {}
+
+pub struct KingOfTheHill {
+    /// Middle of the hill, across the ground
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl MatchMode for KingOfTheHill {
+    fn kill_points(&self, _teamkill: bool) -> f32 {
+        0.0
+    }
+
+    fn award(&mut self, contenders: &[Contender], delta: f32, standings: &mut [Standing]) {
+        let mut on_hill = contenders
+            .iter()
+            .filter(|tank| tank.position.xz().distance(self.center) <= self.radius)
+            .map(|tank| tank.side);
+
+        // A contested hill scores nothing.
+        let Some(holder) = on_hill.next() else {
+            return;
+        };
+
+        if on_hill.all(|side| side == holder) {
+            if let Some(standing) = standings
+                .iter_mut()
+                .find(|standing| standing.side == holder)
+            {
+                standing.score += delta;
+            }
+        }
+    }
+}
+
+pub struct LastTankStanding;
+
+impl MatchMode for LastTankStanding {
+    fn has_score_limit(&self) -> bool {
+        false
+    }
+
+    /// Survivors first, then the sides that lasted longest, then kills.
+    fn rank(&self, a: &Standing, b: &Standing) -> Ordering {
+        let eliminated = |standing: &Standing| standing.eliminated_at.unwrap_or(f32::INFINITY);
+
+        b.alive
+            .cmp(&a.alive)
+            .then(eliminated(b).total_cmp(&eliminated(a)))
+            .then(b.kills.cmp(&a.kills))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchPhase {
+    /// Waiting for the tanks to spawn
+    Waiting,
+    Running,
+    Ended,
+}
+
+/// Why a match ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchEndReason {
+    TimeLimit,
+    ScoreLimit,
+    /// Only one side has tanks left
+    Eliminated,
+}
+
+/// Sent on the first tick with tanks in the arena.
+#[derive(Event, Clone, Debug)]
+pub struct MatchStarted {
+    pub sides: Vec<Side>,
+}
+
+/// Sent once the match has been won or run out of time.
+#[derive(Event, Clone, Debug)]
+pub struct MatchEnded {
+    pub reason: MatchEndReason,
+    /// Seconds the match lasted
+    pub duration: f32,
+    /// Winner first
+    pub standings: Vec<Standing>,
+}
+
+/// The match being played.
+#[derive(Resource)]
+pub struct Match {
+    pub rules: MatchRules,
+    pub mode: Box<dyn MatchMode>,
+    pub phase: MatchPhase,
+    /// Seconds since the match started
+    pub elapsed: f32,
+    pub standings: Vec<Standing>,
+    /// Whether destroyed tanks respawn, in which case losing every tank doesn't end the match
+    pub respawns: bool,
+    /// The side of every tank that has taken part, including destroyed ones
+    sides: HashMap<Entity, Side>,
+}
+
+impl Match {
+    pub fn new(rules: MatchRules) -> Self {
+        let mode = rules.mode.build(&rules);
+        Self::with_mode(rules, mode)
+    }
+
+    /// A match scored by a custom [`MatchMode`] instead of `rules.mode`.
+    pub fn with_mode(rules: MatchRules, mode: Box<dyn MatchMode>) -> Self {
+        Self {
+            rules,
+            mode,
+            phase: MatchPhase::Waiting,
+            elapsed: 0.0,
+            standings: Vec::new(),
+            respawns: false,
+            sides: HashMap::default(),
+        }
+    }
+
+    fn standing_mut(&mut self, side: Side) -> &mut Standing {
+        match self
+            .standings
+            .iter()
+            .position(|standing| standing.side == side)
+        {
+            Some(index) => &mut self.standings[index],
+            None => {
+                self.standings.push(Standing::new(side));
+                self.standings.last_mut().unwrap()
+            }
+        }
+    }
+
+    fn record_kill(&mut self, event: &TankDestroyed) {
+        let victim = self.sides.get(&event.victim).copied();
+        let shooter = self.sides.get(&event.shooter).copied();
+
+        if let Some(victim) = victim {
+            self.standing_mut(victim).deaths += 1;
+        }
+
+        if let Some(shooter) = shooter {
+            let teamkill = victim == Some(shooter);
+            let points = self.mode.kill_points(teamkill);
+            let standing = self.standing_mut(shooter);
+            standing.score += points;
+
+            // Kills break ties, so teamkills mustn't count towards them.
+            if !teamkill {
+                standing.kills += 1;
+            }
+        }
+    }
+
+    fn end_reason(&self) -> Option<MatchEndReason> {
+        let sides_left = self
+            .standings
+            .iter()
+            .filter(|standing| standing.alive > 0)
+            .count();
+
+        let rules = &self.rules;
+        let score_reached = rules.score_limit > 0.0
+            && self.mode.has_score_limit()
+            && self
+                .standings
+                .iter()
+                .any(|standing| standing.score >= rules.score_limit);
+
+        // A match that started with a single side has no one to eliminate.
+        if !self.respawns && self.standings.len() > 1 && sides_left < 2 {
+            Some(MatchEndReason::Eliminated)
+        } else if score_reached {
+            Some(MatchEndReason::ScoreLimit)
+        } else if rules.time_limit > 0.0 && self.elapsed >= rules.time_limit {
+            Some(MatchEndReason::TimeLimit)
+        } else {
+            None
+        }
+    }
+}
+
+/// The parts of a tank that decide its side and whether it holds ground.
+#[derive(WorldQuery)]
+pub struct ContenderQuery {
+    entity: Entity,
+    transform: &'static Transform,
+    ai_tank: Option<&'static AiTank>,
+    team: Option<&'static Team>,
+    health: &'static Health,
+}
+
+pub fn run_match(
+    time: Res<Time>,
+    current: ResMut<Match>,
+    mut destroyed: EventReader<TankDestroyed>,
+    mut started: EventWriter<MatchStarted>,
+    mut ended: EventWriter<MatchEnded>,
+    tanks: Query<ContenderQuery>,
+) {
+    let current = current.into_inner();
+
+    if current.phase == MatchPhase::Ended {
+        destroyed.clear();
+        return;
+    }
+
+    let contenders: Vec<_> = tanks
+        .iter()
+        .filter_map(|tank| {
+            // The player is tank 0.
+            let id = tank.ai_tank.map_or(0, |ai_tank| ai_tank.id);
+            let side = current.mode.side(id, tank.team.copied());
+            current.sides.insert(tank.entity, side);
+
+            // Tanks destroyed this tick are only despawned at the end of it.
+            (tank.health.current > 0.0).then_some(Contender {
+                side,
+                position: tank.transform.translation,
+            })
+        })
+        .collect();
+
+    if current.phase == MatchPhase::Waiting {
+        if contenders.is_empty() {
+            return;
+        }
+
+        for contender in &contenders {
+            current.standing_mut(contender.side);
+        }
+
+        current.phase = MatchPhase::Running;
+        started.send(MatchStarted {
+            sides: current
+                .standings
+                .iter()
+                .map(|standing| standing.side)
+                .collect(),
+        });
+    } else {
+        current.elapsed += time.delta_seconds();
+    }
+
+    for event in destroyed.read() {
+        current.record_kill(event);
+    }
+
+    // Count the survivors, noting when each side lost its last tank.
+
+    let elapsed = current.elapsed;
+
+    for standing in &mut current.standings {
+        standing.alive = 0;
+    }
+
+    for contender in &contenders {
+        current.standing_mut(contender.side).alive += 1;
+    }
+
+    for standing in &mut current.standings {
+        if standing.alive > 0 {
+            standing.eliminated_at = None;
+        } else if standing.eliminated_at.is_none() {
+            standing.eliminated_at = Some(elapsed);
+        }
+    }
+
+    current
+        .mode
+        .award(&contenders, time.delta_seconds(), &mut current.standings);
+
+    let Some(reason) = current.end_reason() else {
+        return;
+    };
+
+    let Match {
+        mode, standings, ..
+    } = current;
+    standings.sort_by(|a, b| mode.rank(a, b));
+
+    info!("match ended after {elapsed:.1}s: {reason:?}");
+
+    for (place, standing) in (1..).zip(standings.iter()) {
+        info!(
+            "{place}. {}: score {:.1}, {} kills, {} deaths, {} left",
+            standing.side, standing.score, standing.kills, standing.deaths, standing.alive
+        );
+    }
+
+    current.phase = MatchPhase::Ended;
+    ended.send(MatchEnded {
+        reason,
+        duration: elapsed,
+        standings: current.standings.clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn spawn_tank(world: &mut World, id: u32) -> Entity {
+        world
+            .spawn((
+                Transform::default(),
+                AiTank {
+                    id,
+                    material: Handle::default(),
+                },
+                Health::new(10.0),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn tanks_destroyed_this_tick_are_not_left_standing() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<TankDestroyed>>();
+        world.init_resource::<Events<MatchStarted>>();
+        world.init_resource::<Events<MatchEnded>>();
+        world.insert_resource(Match::new(MatchRules {
+            mode: GameMode::LastTankStanding,
+            ..default()
+        }));
+
+        let shooter = spawn_tank(&mut world, 1);
+        let victim = spawn_tank(&mut world, 2);
+
+        world.run_system_once(run_match);
+        assert_eq!(world.resource::<Match>().phase, MatchPhase::Running);
+
+        // Destroyed, but not despawned until the end of the tick.
+        world.get_mut::<Health>(victim).unwrap().current = 0.0;
+        world.send_event(TankDestroyed { shooter, victim });
+
+        world.run_system_once(run_match);
+
+        let current = world.resource::<Match>();
+        assert_eq!(current.phase, MatchPhase::Ended);
+        assert_eq!(current.standings[0].side, Side::Tank(1));
+        assert_eq!(current.standings[1].side, Side::Tank(2));
+        assert_eq!(current.standings[1].alive, 0);
+        assert_eq!(current.standings[1].deaths, 1);
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::{
+    pbr::{CascadeShadowConfigBuilder, DirectionalLightShadowMap},
+    prelude::*,
+};
+
+use crate::{
+    assets::GameAssets,
+    config::ScenarioConfig,
+    spawn::{SpawnPoints, TankSpawner},
+    state::GameState,
+    team::TeamAssignments,
+    terrain::Terrain,
+};
+
+/// Spawns the sun, and the tanks once their assets have loaded, each a hull with a turret and
+/// barrel as children.
+pub struct SetupPlugin;
+
+impl Plugin for SetupPlugin {
+    fn build(&self, app: &mut App) {
+        let scenario = app
+            .world
+            .get_resource_or_insert_with(ScenarioConfig::default);
+
+        let shadow_map = DirectionalLightShadowMap {
+            size: scenario.shadow_map_size,
+        };
+
+        app.insert_resource(shadow_map)
+            .init_resource::<Terrain>()
+            .init_resource::<TeamAssignments>()
+            .init_resource::<SpawnPoints>()
+            .init_resource::<GameAssets>()
+            .add_systems(Startup, spawn_sun)
+            .add_systems(OnExit(GameState::Loading), spawn_tanks);
+    }
+}
+
+pub fn spawn_sun(mut commands: Commands) {
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            shadows_enabled: true,
+            ..Default::default()
+        },
+        cascade_shadow_config: CascadeShadowConfigBuilder {
+            num_cascades: 1,
+            maximum_distance: 80.0,
+            ..Default::default()
+        }
+        .into(),
+        transform: Transform::default().looking_at(Vec3::new(0.717, -0.717, 0.0), Vec3::Y),
+        ..default()
+    });
+}
+
+/// Spawns the player and the AI tanks on their spawn points.
+pub fn spawn_tanks(mut tanks: TankSpawner) {
+    tanks.spawn_all();
+}
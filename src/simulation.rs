@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use bevy::{app::AppExit, prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    config::{RunSettings, ScenarioConfig},
+    projectile::{Pooled, Velocity},
+    state::GameState,
+    tank::{AiTank, PlayerTank},
+};
+
+/// Runs the simulation in `FixedUpdate`, so it advances by the same amount every tick no matter
+/// the frame rate. With `--ticks` the simulation stops after exactly that many ticks, which makes
+/// the final state a pure function of the scenario; a checksum of it is logged on exit.
+///
+/// Headless runs and runs limited by `--frames` or `--ticks` advance time by exactly one tick per
+/// frame instead of following the wall clock, so they run as fast as the machine allows and every
+/// frame does simulation work.
+///
+/// Nothing is simulated outside of [`GameState::Playing`].
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        let scenario = app
+            .world
+            .get_resource_or_insert_with(ScenarioConfig::default);
+
+        let tick_rate = scenario.tick_rate;
+        let fixed_time = Time::<Fixed>::from_hz(tick_rate);
+
+        let run_settings = app.world.get_resource_or_insert_with(RunSettings::default);
+
+        if run_settings.headless || run_settings.frames.is_some() || run_settings.ticks.is_some() {
+            let tick = Duration::from_secs_f64(1.0 / tick_rate);
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(tick));
+        }
+
+        app.insert_resource(fixed_time)
+            .init_resource::<SimulationTick>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    SimulationSet::Tanks,
+                    SimulationSet::Bounds,
+                    SimulationSet::Index,
+                    SimulationSet::Hits,
+                    SimulationSet::Projectiles,
+                    SimulationSet::Rules,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing).and_then(ticks_remaining)),
+            )
+            .add_systems(
+                FixedUpdate,
+                advance_tick
+                    .after(SimulationSet::Rules)
+                    .run_if(in_state(GameState::Playing).and_then(ticks_remaining)),
+            )
+            .add_systems(
+                Last,
+                log_state_checksum.run_if(on_event::<AppExit>().and_then(tick_limited)),
+            );
+    }
+}
+
+/// The simulation systems run in `FixedUpdate` in this order.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    Tanks,
+    Bounds,
+    Index,
+    Hits,
+    Projectiles,
+    Rules,
+}
+
+/// The number of fixed ticks simulated so far.
+#[derive(Resource, Default)]
+pub struct SimulationTick(pub u32);
+
+pub fn ticks_remaining(run_settings: Res<RunSettings>, tick: Res<SimulationTick>) -> bool {
+    run_settings.ticks.is_none_or(|ticks| tick.0 < ticks)
+}
+
+fn tick_limited(run_settings: Res<RunSettings>) -> bool {
+    run_settings.ticks.is_some()
+}
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+pub type SimulatedFilter = Or<(With<AiTank>, With<PlayerTank>, With<Velocity>)>;
+
+/// Hashes the exact positions of all tanks and active cannonballs. Entity ids and query order are
+/// not part of the hash, since they depend on how despawns were scheduled across threads. The hash
+/// is FNV-1a rather than std's hasher, whose algorithm may change between Rust releases, so runs
+/// can be compared across toolchain upgrades.
+pub fn state_checksum(query: &Query<(&Transform, Option<&Pooled>), SimulatedFilter>) -> u64 {
+    let mut positions: Vec<_> = query
+        .iter()
+        .filter(|(_, pooled)| Pooled::is_active(*pooled))
+        .map(|(transform, _)| transform.translation.to_array().map(f32::to_bits))
+        .collect();
+
+    positions.sort_unstable();
+
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    positions
+        .iter()
+        .flatten()
+        .flat_map(|bits| bits.to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+fn log_state_checksum(
+    tick: Res<SimulationTick>,
+    query: Query<(&Transform, Option<&Pooled>), SimulatedFilter>,
+) {
+    info!(
+        "state checksum after {} ticks: {:016x}",
+        tick.0,
+        state_checksum(&query)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::{ecs::system::SystemState, log::LogPlugin};
+
+    use super::*;
+    use crate::{headless::HeadlessPlugins, TanksPlugin};
+
+    /// Runs a headless app for exactly `ticks` ticks and returns the checksum of the final state.
+    fn run_ticks(ticks: u32) -> u64 {
+        let mut app = App::new();
+        app.add_plugins(HeadlessPlugins.build().disable::<LogPlugin>())
+            .insert_resource(RunSettings {
+                headless: true,
+                ticks: Some(ticks),
+                ..default()
+            })
+            .insert_resource(ScenarioConfig::default())
+            .add_plugins(TanksPlugin);
+
+        app.finish();
+        app.cleanup();
+
+        // The assets load on other threads, so give them time rather than a number of frames.
+        let deadline = Instant::now() + Duration::from_secs(120);
+
+        while app.world.resource::<SimulationTick>().0 < ticks {
+            assert!(Instant::now() < deadline, "timed out before {ticks} ticks");
+            app.update();
+        }
+
+        assert_eq!(app.world.resource::<SimulationTick>().0, ticks);
+
+        let mut state = SystemState::<Query<(&Transform, Option<&Pooled>), SimulatedFilter>>::new(
+            &mut app.world,
+        );
+        state_checksum(&state.get(&app.world))
+    }
+
+    #[test]
+    fn same_scenario_and_ticks_give_the_same_state() {
+        assert_eq!(run_ticks(300), run_ticks(300));
+    }
+}
@@ -0,0 +1,397 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    combat::{Health, HitVolumes},
+    config::ScenarioConfig,
+    projectile::{Cannonball, Pooled},
+    simulation::SimulationSet,
+};
+
+/// Rebuilds the [`SpatialIndex`] of tanks and cannonballs every tick.
+pub struct SpatialIndexPlugin;
+
+impl Plugin for SpatialIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScenarioConfig>()
+            .init_resource::<SpatialIndex>()
+            .add_systems(
+                FixedUpdate,
+                rebuild_spatial_index
+                    .in_set(SimulationSet::Index)
+                    .run_if(resource_exists::<HitVolumes>()),
+            );
+    }
+}
+
+/// Tanks and cannonballs by position, rebuilt every tick. They are kept in separate grids so
+/// queries for one kind don't have to skip over the other.
+///
+/// All queries take `&self`, so the index can be read from `par_iter` systems.
+#[derive(Resource)]
+pub struct SpatialIndex {
+    pub tanks: SpatialGrid,
+    pub cannonballs: SpatialGrid,
+}
+
+impl FromWorld for SpatialIndex {
+    fn from_world(world: &mut World) -> Self {
+        let cell_size = world
+            .get_resource::<ScenarioConfig>()
+            .map_or(ScenarioConfig::default().spatial_cell_size, |scenario| {
+                scenario.spatial_cell_size
+            });
+
+        Self {
+            tanks: SpatialGrid::new(cell_size),
+            cannonballs: SpatialGrid::new(cell_size),
+        }
+    }
+}
+
+/// An entity in a [`SpatialGrid`], approximated by a bounding sphere.
+#[derive(Clone, Copy, Debug)]
+pub struct SpatialEntry {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+/// A uniform hash grid over the XZ plane. Each entry is stored in the cell containing its center,
+/// and queries widen their search by the largest radius in the grid to compensate.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<SpatialEntry>>,
+    max_radius: f32,
+    /// Corners of the box around every entry's center
+    min: Vec3,
+    max: Vec3,
+    len: usize,
+}
+
+impl SpatialGrid {
+    /// Panics unless `cell_size` is positive and finite, since queries would visit an unbounded
+    /// number of cells otherwise.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(
+            cell_size.is_finite() && cell_size > 0.0,
+            "spatial grid cell size must be positive, got {cell_size}"
+        );
+
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            max_radius: 0.0,
+            min: Vec3::INFINITY,
+            max: Vec3::NEG_INFINITY,
+            len: 0,
+        }
+    }
+
+    /// Removes all entries, keeping the allocated cells for the next rebuild.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+
+        self.max_radius = 0.0;
+        self.min = Vec3::INFINITY;
+        self.max = Vec3::NEG_INFINITY;
+        self.len = 0;
+    }
+
+    pub fn insert(&mut self, entry: SpatialEntry) {
+        let cell = self.cell(entry.position);
+        self.cells.entry(cell).or_default().push(entry);
+
+        self.max_radius = self.max_radius.max(entry.radius);
+        self.min = self.min.min(entry.position);
+        self.max = self.max.max(entry.position);
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Entries whose bounding sphere overlaps the sphere at `center`.
+    pub fn query_radius(
+        &self,
+        center: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = &SpatialEntry> + '_ {
+        let reach = Vec3::splat(radius);
+
+        self.entries_near(center - reach, center + reach)
+            .filter(move |entry| {
+                let max_distance = radius + entry.radius;
+                entry.position.distance_squared(center) <= max_distance * max_distance
+            })
+    }
+
+    /// Entries whose bounding sphere overlaps the box from `min` to `max`.
+    pub fn query_aabb(&self, min: Vec3, max: Vec3) -> impl Iterator<Item = &SpatialEntry> + '_ {
+        self.entries_near(min, max).filter(move |entry| {
+            let closest = entry.position.clamp(min, max);
+            entry.position.distance_squared(closest) <= entry.radius * entry.radius
+        })
+    }
+
+    /// Entries whose bounding sphere is crossed by the ray within `max_distance`, nearest first,
+    /// along with the distance at which the ray enters them. An infinite `max_distance` reaches
+    /// every entry along the ray, and a NaN one none.
+    pub fn query_ray(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Vec<(f32, &SpatialEntry)> {
+        if max_distance.is_nan() {
+            return Vec::new();
+        }
+
+        let direction = direction.normalize_or_zero();
+
+        // Nothing lies beyond the farthest entry, so don't sample the ray past it.
+        let max_distance = max_distance.min(self.farthest_reach(origin));
+
+        // Sample the ray every half cell and visit the cells around each sample that could hold
+        // an entry reaching the ray.
+        let reach = (self.max_radius / self.cell_size).ceil() as i32 + 1;
+        let steps = (max_distance / (self.cell_size * 0.5)).ceil() as u32;
+
+        let mut cells = Vec::new();
+
+        for step in 0..=steps {
+            let distance = (step as f32 * self.cell_size * 0.5).min(max_distance);
+            let cell = self.cell(origin + direction * distance);
+
+            for x in -reach..=reach {
+                for z in -reach..=reach {
+                    cells.push(cell + IVec2::new(x, z));
+                }
+            }
+        }
+
+        cells.sort_unstable_by_key(|cell| (cell.x, cell.y));
+        cells.dedup();
+
+        let mut hits: Vec<_> = cells
+            .iter()
+            .filter_map(|cell| self.cells.get(cell))
+            .flatten()
+            .filter_map(|entry| {
+                let distance =
+                    ray_sphere_distance(origin, direction, entry.position, entry.radius)?;
+                (distance <= max_distance).then_some((distance, entry))
+            })
+            .collect();
+
+        hits.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        hits
+    }
+
+    /// How far from `origin` an entry could reach at most.
+    fn farthest_reach(&self, origin: Vec3) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let farthest_corner = (origin - self.min).abs().max((origin - self.max).abs());
+        farthest_corner.length() + self.max_radius
+    }
+
+    fn cell(&self, position: Vec3) -> IVec2 {
+        (Vec2::new(position.x, position.z) / self.cell_size)
+            .floor()
+            .as_ivec2()
+    }
+
+    /// Every entry in the cells that could overlap the box from `min` to `max`.
+    fn entries_near(&self, min: Vec3, max: Vec3) -> impl Iterator<Item = &SpatialEntry> + '_ {
+        let reach = Vec3::splat(self.max_radius);
+        let min = self.cell(min - reach);
+        let max = self.cell(max + reach);
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |z| IVec2::new(x, z)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+}
+
+/// Distance along a normalized ray to where it enters a sphere, or zero if it starts inside.
+fn ray_sphere_distance(origin: Vec3, direction: Vec3, center: Vec3, radius: f32) -> Option<f32> {
+    let to_center = center - origin;
+    let along = to_center.dot(direction);
+    let off_ray_squared = to_center.length_squared() - along * along;
+    let radius_squared = radius * radius;
+
+    if off_ray_squared > radius_squared {
+        return None;
+    }
+
+    let entry = along - (radius_squared - off_ray_squared).sqrt();
+    let exit = along + (radius_squared - off_ray_squared).sqrt();
+
+    (exit >= 0.0).then_some(entry.max(0.0))
+}
+
+pub fn rebuild_spatial_index(
+    mut spatial_index: ResMut<SpatialIndex>,
+    hit_volumes: Res<HitVolumes>,
+    tanks: Query<(Entity, &Transform), With<Health>>,
+    cannonballs: Query<(Entity, &Transform, Option<&Pooled>), With<Cannonball>>,
+) {
+    let SpatialIndex {
+        tanks: tank_grid,
+        cannonballs: cannonball_grid,
+    } = spatial_index.as_mut();
+
+    tank_grid.clear();
+    cannonball_grid.clear();
+
+    let tank_radius = hit_volumes.tank_radius();
+
+    for (entity, transform) in &tanks {
+        tank_grid.insert(SpatialEntry {
+            entity,
+            position: transform.translation,
+            radius: tank_radius,
+        });
+    }
+
+    for (entity, transform, pooled) in &cannonballs {
+        if !Pooled::is_active(pooled) {
+            continue;
+        }
+
+        cannonball_grid.insert(SpatialEntry {
+            entity,
+            position: transform.translation,
+            radius: hit_volumes.cannonball_radius,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARENA_SIZE: f32 = 60.0;
+
+    /// Entries scattered over the arena, with a few that are larger than a cell.
+    fn random_entries(count: u32) -> Vec<SpatialEntry> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        };
+
+        (0..count)
+            .map(|i| SpatialEntry {
+                entity: Entity::from_raw(i),
+                position: Vec3::new(
+                    (random() - 0.5) * ARENA_SIZE,
+                    random() * 5.0,
+                    (random() - 0.5) * ARENA_SIZE,
+                ),
+                radius: if i % 10 == 0 { 6.0 } else { 0.2 + random() },
+            })
+            .collect()
+    }
+
+    fn grid(entries: &[SpatialEntry]) -> SpatialGrid {
+        let mut grid = SpatialGrid::new(4.0);
+
+        for entry in entries {
+            grid.insert(*entry);
+        }
+
+        grid
+    }
+
+    fn sorted_entities<'a>(entries: impl Iterator<Item = &'a SpatialEntry>) -> Vec<Entity> {
+        let mut entities: Vec<_> = entries.map(|entry| entry.entity).collect();
+        entities.sort_unstable();
+        entities
+    }
+
+    #[test]
+    fn query_aabb_matches_brute_force() {
+        let entries = random_entries(500);
+        let grid = grid(&entries);
+
+        for (min, max) in [
+            (Vec3::new(-5.0, 0.0, -5.0), Vec3::new(5.0, 5.0, 5.0)),
+            (Vec3::new(-40.0, -1.0, 10.0), Vec3::new(-20.0, 1.0, 12.0)),
+            (Vec3::new(29.0, 0.0, -29.0), Vec3::new(29.5, 0.5, -28.5)),
+            (Vec3::splat(-100.0), Vec3::splat(100.0)),
+        ] {
+            let expected = sorted_entities(entries.iter().filter(|entry| {
+                let closest = entry.position.clamp(min, max);
+                entry.position.distance_squared(closest) <= entry.radius * entry.radius
+            }));
+
+            assert_eq!(sorted_entities(grid.query_aabb(min, max)), expected);
+        }
+    }
+
+    #[test]
+    fn query_ray_matches_brute_force() {
+        let entries = random_entries(500);
+        let grid = grid(&entries);
+
+        for (origin, direction, max_distance) in [
+            (Vec3::new(0.0, 1.0, 0.0), Vec3::X, 20.0),
+            (
+                Vec3::new(-30.0, 2.0, -30.0),
+                Vec3::new(1.0, 0.0, 1.0),
+                100.0,
+            ),
+            (Vec3::new(10.0, 4.0, -5.0), Vec3::new(-0.3, -0.1, 0.8), 35.0),
+            (Vec3::new(0.0, 50.0, 0.0), Vec3::NEG_Y, 60.0),
+            (Vec3::new(-80.0, 1.0, 3.0), Vec3::X, f32::INFINITY),
+        ] {
+            let normalized = direction.normalize();
+            let mut expected: Vec<_> = entries
+                .iter()
+                .filter_map(|entry| {
+                    let distance =
+                        ray_sphere_distance(origin, normalized, entry.position, entry.radius)?;
+                    (distance <= max_distance).then_some((distance, entry.entity))
+                })
+                .collect();
+
+            let mut hits: Vec<_> = grid
+                .query_ray(origin, direction, max_distance)
+                .into_iter()
+                .map(|(distance, entry)| (distance, entry.entity))
+                .collect();
+
+            assert!(hits.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+            // Entries the ray starts inside are all at distance zero, in no particular order.
+            let by_distance = |(a, a_entity): &(f32, Entity), (b, b_entity): &(f32, Entity)| {
+                a.total_cmp(b).then(a_entity.cmp(b_entity))
+            };
+            expected.sort_by(by_distance);
+            hits.sort_by(by_distance);
+
+            assert!(!expected.is_empty());
+            assert_eq!(hits, expected);
+        }
+    }
+
+    #[test]
+    fn query_ray_with_nan_distance_hits_nothing() {
+        let grid = grid(&random_entries(100));
+
+        assert!(grid.query_ray(Vec3::ZERO, Vec3::X, f32::NAN).is_empty());
+    }
+}
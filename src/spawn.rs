@@ -0,0 +1,446 @@
+use std::{
+    f32::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::Noise,
+    assets::GameAssets,
+    ballistics::Arc,
+    brain::{Brain, Marksman, NoiseWander},
+    combat::{Health, TankDestroyed},
+    config::ScenarioConfig,
+    simulation::SimulationSet,
+    tank::{AiTank, PlayerTank, Suspension, TankMotion, TankVelocity},
+    team::{assign_teams, TeamAssignments},
+    terrain::Terrain,
+    turret::spawn_turret,
+    weapon::Weapon,
+};
+
+/// How far inside the arena walls random spawn points are kept
+const RANDOM_SPAWN_MARGIN: f32 = 10.0;
+
+/// Tries to place each random spawn point this many times before giving up on the spacing
+const RANDOM_SPAWN_ATTEMPTS: u32 = 100;
+
+/// Places tanks on the scenario's spawn points, and brings destroyed tanks back after the
+/// `respawn_delay` with a moment of spawn protection.
+pub struct SpawnPlugin;
+
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScenarioConfig>()
+            .init_resource::<Noise>()
+            .init_resource::<Terrain>()
+            .init_resource::<TeamAssignments>()
+            .init_resource::<SpawnPoints>()
+            .init_resource::<Respawns>()
+            .add_event::<TankDestroyed>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    tick_spawn_protection.in_set(SimulationSet::Tanks),
+                    (queue_respawns, respawn_tanks)
+                        .chain()
+                        .in_set(SimulationSet::Rules),
+                ),
+            );
+    }
+}
+
+/// Where tanks start, set with `spawn_layout` in the scenario.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpawnLayout {
+    /// Around a ring, `spawn_spacing` apart and facing outwards
+    Ring,
+    /// On a square grid with `spawn_spacing` between rows and columns
+    Grid,
+    /// Scattered across the arena, at least `spawn_spacing` apart where there is room
+    Random,
+    /// Read from a RON file with a list of `(position: (x, z), heading: angle)` points
+    Map(PathBuf),
+}
+
+impl FromStr for SpawnLayout {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "ring" => Ok(Self::Ring),
+            "grid" => Ok(Self::Grid),
+            "random" => Ok(Self::Random),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A place for a tank to start.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnPoint {
+    /// Position across the ground
+    pub position: Vec2,
+    /// Angle about the Y axis to face
+    #[serde(default)]
+    pub heading: f32,
+}
+
+impl SpawnPoint {
+    /// Faces away from the middle of the arena, so tanks don't start out driving into each other.
+    fn facing_out(position: Vec2) -> Self {
+        Self {
+            position,
+            heading: position.x.atan2(position.y),
+        }
+    }
+
+    /// A tank settled on the terrain at this point.
+    pub fn place(&self, terrain: &Terrain) -> (Transform, Suspension) {
+        let mut transform =
+            Transform::from_translation(Vec3::new(self.position.x, 0.0, self.position.y));
+
+        // Start settled on the slope rather than swinging into place on the first tick.
+        let mut suspension = Suspension::default();
+        suspension.ride(&mut transform, terrain, self.heading, f32::INFINITY, 1.0);
+        (transform, suspension)
+    }
+}
+
+/// Reads the spawn points of a [`SpawnLayout::Map`].
+pub fn load_spawn_map(path: &Path) -> Result<Vec<SpawnPoint>, String> {
+    let display = path.display();
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("failed to read {display}: {err}"))?;
+    let points: Vec<SpawnPoint> =
+        ron::from_str(&contents).map_err(|err| format!("failed to parse {display}: {err}"))?;
+
+    if points.is_empty() {
+        return Err(format!("{display} has no spawn points"));
+    }
+
+    Ok(points)
+}
+
+/// The spawn points of the scenario, one per tank unless a map has fewer.
+#[derive(Resource)]
+pub struct SpawnPoints {
+    pub points: Vec<SpawnPoint>,
+}
+
+impl FromWorld for SpawnPoints {
+    fn from_world(world: &mut World) -> Self {
+        let scenario = world.get_resource_or_insert_with(ScenarioConfig::default);
+        let count = assign_teams(&scenario).len() as u32;
+        let spacing = scenario.spawn_spacing;
+
+        let points = match &scenario.spawn_layout {
+            SpawnLayout::Ring => ring(count, spacing),
+            SpawnLayout::Grid => grid(count, spacing),
+            SpawnLayout::Random => random(count, spacing, scenario.floor_size / 2.0, scenario.seed),
+            SpawnLayout::Map(path) => match load_spawn_map(path) {
+                Ok(points) => {
+                    if points.len() < count as usize {
+                        warn!(
+                            "{} has {} spawn points for {count} tanks, some will start together",
+                            path.display(),
+                            points.len()
+                        );
+                    }
+
+                    points
+                }
+                Err(err) => {
+                    error!("{err}, spawning tanks around a ring instead");
+                    ring(count, spacing)
+                }
+            },
+        };
+
+        Self { points }
+    }
+}
+
+impl SpawnPoints {
+    /// The spawn point of a tank at the start of the match.
+    pub fn get(&self, id: u32) -> SpawnPoint {
+        self.points[id as usize % self.points.len()]
+    }
+
+    /// The spawn point furthest from any of the `occupied` positions, to respawn out of harm's
+    /// way.
+    pub fn furthest_from(&self, occupied: &[Vec3]) -> SpawnPoint {
+        let clearance = |point: &SpawnPoint| {
+            occupied
+                .iter()
+                .map(|position| position.xz().distance_squared(point.position))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        *self
+            .points
+            .iter()
+            .max_by(|a, b| clearance(a).total_cmp(&clearance(b)))
+            .unwrap()
+    }
+}
+
+fn ring(count: u32, spacing: f32) -> Vec<SpawnPoint> {
+    let radius = count as f32 * spacing / (2.0 * PI);
+
+    (0..count.max(1))
+        .map(|id| {
+            let angle = id as f32 / count.max(1) as f32 * 2.0 * PI;
+
+            SpawnPoint {
+                position: Vec2::new(angle.sin(), angle.cos()) * radius,
+                heading: angle,
+            }
+        })
+        .collect()
+}
+
+fn grid(count: u32, spacing: f32) -> Vec<SpawnPoint> {
+    let columns = (count as f32).sqrt().ceil().max(1.0) as u32;
+    let rows = count.div_ceil(columns).max(1);
+    let center = UVec2::new(columns - 1, rows - 1).as_vec2() / 2.0;
+
+    (0..count.max(1))
+        .map(|id| {
+            let cell = UVec2::new(id % columns, id / columns).as_vec2();
+            SpawnPoint::facing_out((cell - center) * spacing)
+        })
+        .collect()
+}
+
+fn random(count: u32, spacing: f32, half_size: f32, seed: u32) -> Vec<SpawnPoint> {
+    let extent = (half_size - RANDOM_SPAWN_MARGIN).max(0.0);
+    let mut state = seed as u64;
+    let mut points: Vec<SpawnPoint> = Vec::new();
+    let mut crowded = false;
+
+    for _ in 0..count.max(1) {
+        let mut attempts = 0;
+
+        let position = loop {
+            let unit = Vec2::new(unit_random(&mut state), unit_random(&mut state));
+            let position = (unit * 2.0 - 1.0) * extent;
+            attempts += 1;
+
+            let clear = points
+                .iter()
+                .all(|point| point.position.distance(position) >= spacing);
+
+            if clear {
+                break position;
+            }
+
+            if attempts == RANDOM_SPAWN_ATTEMPTS {
+                crowded = true;
+                break position;
+            }
+        };
+
+        points.push(SpawnPoint::facing_out(position));
+    }
+
+    if crowded {
+        warn!("not enough room for {count} tanks {spacing} apart, some will start closer");
+    }
+
+    points
+}
+
+/// A number in the range 0..1 from a step of the SplitMix64 generator, which is plenty for
+/// scattering spawn points and keeps them the same for the same seed.
+fn unit_random(state: &mut u64) -> f32 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+
+    // The top 24 bits fill an f32's mantissa exactly.
+    (z >> 40) as f32 / (1 << 24) as f32
+}
+
+/// Makes a tank immune to cannonballs for a moment after it respawns.
+#[derive(Component)]
+pub struct SpawnProtection {
+    /// Seconds until the tank can be hit
+    pub remaining: f32,
+}
+
+/// Spawns tanks from a system: the player as tank 0, and AI tanks driven by the scenario's
+/// brain for the rest.
+#[derive(SystemParam)]
+pub struct TankSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    assets: Res<'w, GameAssets>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    scenario: Res<'w, ScenarioConfig>,
+    terrain: Res<'w, Terrain>,
+    noise: Res<'w, Noise>,
+    teams: Res<'w, TeamAssignments>,
+    pub points: Res<'w, SpawnPoints>,
+}
+
+impl<'w, 's> TankSpawner<'w, 's> {
+    /// Spawns tank `id`, a hull with a turret and barrel as children, at `point`.
+    pub fn spawn(&mut self, id: u32, point: SpawnPoint) -> EntityCommands<'w, 's, '_> {
+        let scenario = &self.scenario;
+        let material = self.materials.add(self.teams.tank_color(id).into());
+        let (transform, suspension) = point.place(&self.terrain);
+
+        let mut tank = self.commands.spawn((
+            PbrBundle {
+                mesh: self.assets.tank_mesh.clone(),
+                material: material.clone(),
+                transform,
+                ..default()
+            },
+            suspension,
+            TankMotion::new(scenario, point.heading),
+            TankVelocity::default(),
+            Health::new(scenario.tank_health),
+            Weapon::new(scenario.weapon),
+        ));
+
+        if id == 0 {
+            tank.insert(PlayerTank);
+        } else {
+            // AI tanks wander with the noise function, shooting at their targets if targeting
+            // is enabled.
+            let ai_tank = AiTank { id, material };
+            let wander = NoiseWander::new(self.noise.generator, &ai_tank);
+            let brain = match scenario.ai_targeting {
+                Some(selection) => Brain::new(Marksman {
+                    wander,
+                    selection,
+                    arc: Arc::Low,
+                }),
+                None => Brain::new(wander),
+            };
+
+            tank.insert((ai_tank, brain));
+        }
+
+        if let Some(team) = self.teams.team(id) {
+            tank.insert(team);
+        }
+
+        spawn_turret(&mut tank);
+        tank
+    }
+
+    /// Spawns every tank of the scenario at its own spawn point.
+    pub fn spawn_all(&mut self) {
+        for id in 0..self.teams.tank_count().max(1) {
+            let point = self.points.get(id);
+            self.spawn(id, point);
+        }
+    }
+}
+
+/// Destroyed tanks waiting to come back.
+#[derive(Resource, Default)]
+pub struct Respawns {
+    pending: Vec<PendingRespawn>,
+}
+
+struct PendingRespawn {
+    id: u32,
+    /// Simulation time to respawn at, in seconds
+    at: f32,
+}
+
+impl Respawns {
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+fn queue_respawns(
+    scenario: Res<ScenarioConfig>,
+    time: Res<Time>,
+    mut respawns: ResMut<Respawns>,
+    mut destroyed: EventReader<TankDestroyed>,
+    tanks: Query<(Option<&AiTank>, Has<PlayerTank>)>,
+) {
+    let Some(delay) = scenario.respawn_delay else {
+        destroyed.clear();
+        return;
+    };
+
+    for event in destroyed.read() {
+        // Destroyed tanks are only despawned at the end of the tick, so they can still be looked
+        // up here.
+        let id = match tanks.get(event.victim) {
+            Ok((Some(tank), _)) => tank.id,
+            Ok((None, true)) => 0,
+            _ => continue,
+        };
+
+        respawns.pending.push(PendingRespawn {
+            id,
+            at: time.elapsed_seconds() + delay,
+        });
+    }
+}
+
+fn respawn_tanks(
+    scenario: Res<ScenarioConfig>,
+    time: Res<Time>,
+    mut respawns: ResMut<Respawns>,
+    mut spawner: TankSpawner,
+    tanks: Query<&Transform, With<Health>>,
+) {
+    let now = time.elapsed_seconds();
+    let mut occupied: Vec<_> = tanks
+        .iter()
+        .map(|transform| transform.translation)
+        .collect();
+
+    respawns.pending.retain(|respawn| {
+        if respawn.at > now {
+            return true;
+        }
+
+        let point = spawner.points.furthest_from(&occupied);
+        occupied.push(Vec3::new(point.position.x, 0.0, point.position.y));
+
+        let mut tank = spawner.spawn(respawn.id, point);
+
+        if scenario.spawn_protection > 0.0 {
+            tank.insert(SpawnProtection {
+                remaining: scenario.spawn_protection,
+            });
+        }
+
+        false
+    });
+}
+
+fn tick_spawn_protection(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut SpawnProtection)>,
+) {
+    for (entity, mut protection) in &mut query {
+        protection.remaining -= time.delta_seconds();
+
+        if protection.remaining <= 0.0 {
+            commands.entity(entity).remove::<SpawnProtection>();
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    assets::assets_loaded,
+    combat::HitVolumes,
+    config::RunSettings,
+    rules::{Match, MatchEnded},
+};
+
+/// Starts the match from the menu
+const START_KEY: KeyCode = KeyCode::Return;
+
+/// Pauses and resumes the match, and quits from the results
+const PAUSE_KEY: KeyCode = KeyCode::Escape;
+
+/// Moves the game from loading through the menu into the match, and on to the results once the
+/// match ends. The simulation only runs while [`GameState::Playing`]; everywhere else the virtual
+/// clock is paused, so the scene keeps rendering but nothing moves.
+///
+/// Headless and benchmark runs skip the menu and start playing as soon as loading is done.
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>()
+            .init_resource::<RunSettings>()
+            .add_event::<MatchEnded>()
+            .add_systems(Startup, pause_time)
+            .add_systems(
+                Update,
+                (
+                    finish_loading.run_if(in_state(GameState::Loading).and_then(assets_loaded)),
+                    start_match.run_if(in_state(GameState::Menu)),
+                    toggle_pause
+                        .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+                    end_match.run_if(in_state(GameState::Playing)),
+                    quit_from_results.run_if(in_state(GameState::Results)),
+                ),
+            )
+            .add_systems(OnEnter(GameState::Playing), resume_time)
+            .add_systems(OnExit(GameState::Playing), pause_time);
+
+        for (state, text) in [
+            (GameState::Menu, "TANKS\n\nEnter to start"),
+            (GameState::Paused, "PAUSED\n\nEsc to resume"),
+        ] {
+            app.add_systems(
+                OnEnter(state),
+                (move |commands: Commands| spawn_overlay(commands, text)).run_if(windowed),
+            )
+            .add_systems(OnExit(state), despawn_overlay);
+        }
+
+        app.add_systems(OnEnter(GameState::Results), show_results.run_if(windowed))
+            .add_systems(OnExit(GameState::Results), despawn_overlay);
+    }
+}
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GameState {
+    /// Waiting for the glTF assets, and the hit volumes computed from them
+    #[default]
+    Loading,
+    Menu,
+    Playing,
+    /// The match is frozen, but still rendered
+    Paused,
+    /// The match has ended and the standings are shown
+    Results,
+}
+
+/// Text shown over the scene outside of play.
+#[derive(Component)]
+struct Overlay;
+
+fn windowed(run_settings: Res<RunSettings>) -> bool {
+    !run_settings.headless
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn finish_loading(
+    run_settings: Res<RunSettings>,
+    hit_volumes: Option<Res<HitVolumes>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if hit_volumes.is_none() {
+        return;
+    }
+
+    // There is no one to press start in headless and benchmark runs.
+    let skip_menu =
+        run_settings.headless || run_settings.frames.is_some() || run_settings.ticks.is_some();
+
+    next_state.set(if skip_menu {
+        GameState::Playing
+    } else {
+        GameState::Menu
+    });
+}
+
+fn start_match(keyboard: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keyboard.just_pressed(START_KEY) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn toggle_pause(
+    keyboard: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard.just_pressed(PAUSE_KEY) {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        GameState::Paused => GameState::Playing,
+        _ => GameState::Paused,
+    });
+}
+
+fn end_match(
+    mut match_ended: EventReader<MatchEnded>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if match_ended.read().count() > 0 {
+        next_state.set(GameState::Results);
+    }
+}
+
+fn quit_from_results(keyboard: Res<Input<KeyCode>>, mut app_exit: EventWriter<AppExit>) {
+    if keyboard.just_pressed(PAUSE_KEY) {
+        app_exit.send(AppExit);
+    }
+}
+
+fn show_results(commands: Commands, current: Option<Res<Match>>) {
+    let mut text = String::from("MATCH OVER\n\n");
+
+    for (place, standing) in (1..).zip(current.iter().flat_map(|current| &current.standings)) {
+        text += &format!(
+            "{place}. {}   score {:.0}   {} kills   {} deaths\n",
+            standing.side, standing.score, standing.kills, standing.deaths
+        );
+    }
+
+    text += "\nEsc to quit";
+    spawn_overlay(commands, &text);
+}
+
+fn spawn_overlay(mut commands: Commands, text: &str) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                ..default()
+            },
+            Overlay,
+        ))
+        .with_children(|overlay| {
+            overlay.spawn(
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+            );
+        });
+}
+
+fn despawn_overlay(mut commands: Commands, query: Query<Entity, With<Overlay>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,191 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{config::ScenarioConfig, terrain::Terrain};
+
+/// Where the corners of the tracks touch the ground, in the tank's local XZ plane
+const TRACK_CONTACTS: [Vec2; 4] = [
+    Vec2::new(-0.5, -0.65),
+    Vec2::new(0.5, -0.65),
+    Vec2::new(-0.5, 0.65),
+    Vec2::new(0.5, 0.65),
+];
+
+#[derive(Component)]
+pub struct AiTank {
+    /// This id seeds the noise function used for movement
+    pub id: u32,
+    pub material: Handle<StandardMaterial>,
+}
+
+#[derive(Component)]
+pub struct PlayerTank;
+
+/// How a tank speeds up, slows down and turns. Drivers set a throttle and a heading to steer
+/// towards, and the tank gets there as fast as its engine and tracks allow.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TankMotion {
+    /// Speed gained per second when speeding up
+    pub acceleration: f32,
+    /// Speed lost per second when slowing down or changing direction
+    pub braking: f32,
+    /// Top speed, forwards or in reverse
+    pub max_speed: f32,
+    /// Radians per second
+    pub max_turn_rate: f32,
+    /// Current speed along the heading, negative in reverse
+    pub speed: f32,
+    /// Current heading, as an angle about the Y axis
+    pub heading: f32,
+}
+
+impl TankMotion {
+    /// A stationary tank with the scenario's engine and tracks.
+    pub fn new(scenario: &ScenarioConfig, heading: f32) -> Self {
+        Self {
+            acceleration: scenario.tank_acceleration,
+            braking: scenario.tank_braking,
+            max_speed: scenario.tank_speed,
+            max_turn_rate: scenario.tank_turn_rate,
+            speed: 0.0,
+            heading,
+        }
+    }
+
+    /// Speeds up or slows down towards `throttle` times the top speed, with `throttle` in the
+    /// range -1..=1.
+    pub fn throttle(&mut self, throttle: f32, delta: f32) {
+        let target = throttle.clamp(-1.0, 1.0) * self.max_speed;
+        let speeding_up = self.speed * target >= 0.0 && target.abs() > self.speed.abs();
+        let rate = if speeding_up {
+            self.acceleration
+        } else {
+            self.braking
+        };
+
+        self.speed += (target - self.speed).clamp(-rate * delta, rate * delta);
+    }
+
+    /// Turns towards `target` the shortest way round, as fast as the tracks allow.
+    pub fn steer_towards(&mut self, target: f32, delta: f32) {
+        let turn =
+            wrap_angle(target - self.heading) / (self.max_turn_rate * delta).max(f32::EPSILON);
+        self.turn(turn, delta);
+    }
+
+    /// Turns at a fraction of the top turn rate, with `turn` in the range -1..=1 and left
+    /// positive.
+    pub fn turn(&mut self, turn: f32, delta: f32) {
+        self.heading =
+            wrap_angle(self.heading + turn.clamp(-1.0, 1.0) * self.max_turn_rate * delta);
+    }
+
+    /// Moves a tank along its heading at its current speed.
+    pub fn drive(&self, translation: &mut Vec3, delta: f32) {
+        *translation += Vec3::new(self.heading.sin(), 0.0, self.heading.cos()) * self.speed * delta;
+    }
+}
+
+/// How fast a tank is actually moving, including climbing and descending slopes.
+#[derive(Component, Default)]
+pub struct TankVelocity {
+    pub val: Vec3,
+}
+
+impl TankVelocity {
+    /// Measures the velocity from how far a tank moved this tick.
+    pub fn measure(&mut self, from: Vec3, to: Vec3, delta: f32) {
+        if delta > 0.0 {
+            self.val = (to - from) / delta;
+        }
+    }
+}
+
+/// Tilts a tank to follow the slope under its tracks. The hull eases towards the ground instead of
+/// snapping to it, like it would on springs.
+#[derive(Component)]
+pub struct Suspension {
+    /// The hull's current up direction
+    pub up: Vec3,
+}
+
+impl Default for Suspension {
+    fn default() -> Self {
+        Self { up: Vec3::Y }
+    }
+}
+
+impl Suspension {
+    /// Places a tank on the terrain with the given heading. The hull rests at the average height
+    /// of its track contacts and tilts towards their average normal, closing `stiffness` of the
+    /// remaining gap per second.
+    pub fn ride(
+        &mut self,
+        transform: &mut Transform,
+        terrain: &Terrain,
+        heading: f32,
+        stiffness: f32,
+        delta: f32,
+    ) {
+        let yaw = Quat::from_axis_angle(Vec3::Y, heading);
+
+        let mut height = 0.0;
+        let mut normal = Vec3::ZERO;
+
+        for contact in TRACK_CONTACTS {
+            let point = transform.translation + yaw * Vec3::new(contact.x, 0.0, contact.y);
+            height += terrain.height_at(point.x, point.z) / TRACK_CONTACTS.len() as f32;
+            normal += terrain.normal_at(point.x, point.z);
+        }
+
+        let blend = 1.0 - (-stiffness * delta).exp();
+        self.up = self.up.lerp(normal.normalize(), blend).normalize();
+
+        transform.translation.y = height;
+        transform.rotation = hull_rotation(heading, self.up);
+    }
+}
+
+/// The rotation of a hull with the given heading and up direction. Seen from above, the hull
+/// still faces exactly `heading`, so tilting it never turns it.
+pub fn hull_rotation(heading: f32, up: Vec3) -> Quat {
+    let horizontal = Vec3::new(heading.sin(), 0.0, heading.cos());
+
+    // Raise or lower the heading until it lies in the plane perpendicular to `up`.
+    let forward = (horizontal - Vec3::Y * horizontal.dot(up) / up.y).normalize();
+    let right = up.cross(forward);
+
+    Quat::from_mat3(&Mat3::from_cols(right, up, forward))
+}
+
+/// Wraps an angle into the range -π..=π, e.g. to turn the shortest way round.
+pub fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// The heading of a tank, as an angle about the Y axis, ignoring any tilt.
+pub fn heading(rotation: Quat) -> f32 {
+    // The tank model faces +Z.
+    let forward = rotation * Vec3::Z;
+    forward.x.atan2(forward.z)
+}
+
+pub fn tank_color(tank_id: u32) -> Color {
+    let hue = (tank_id % 20) as f32 * 18.0;
+    let x = 1.0 - ((hue / 60.0) % 2.0 - 1.0).abs();
+
+    if hue < 60.0 {
+        Color::rgb(1.0, x, 0.0)
+    } else if hue < 120.0 {
+        Color::rgb(x, 1.0, 0.0)
+    } else if hue < 180.0 {
+        Color::rgb(0.0, 1.0, x)
+    } else if hue < 240.0 {
+        Color::rgb(0.0, x, 1.0)
+    } else if hue < 300.0 {
+        Color::rgb(x, 0.0, 1.0)
+    } else {
+        Color::rgb(1.0, 0.0, x)
+    }
+}
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::brain::TankState;
+
+/// Height above a tank's origin to aim at, roughly the middle of the hull
+pub const TARGET_HEIGHT: f32 = 0.6;
+
+/// How a tank picks which enemy to shoot at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetSelection {
+    Nearest,
+    /// Lowest health, then nearest
+    Weakest,
+    /// Tanks that are close, healthy and facing this one first
+    Threat,
+}
+
+impl FromStr for TargetSelection {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "nearest" => Ok(Self::Nearest),
+            "weakest" => Ok(Self::Weakest),
+            "threat" => Ok(Self::Threat),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TargetSelection {
+    /// Picks a target within `max_range` of `position`, measured across the ground.
+    pub fn select<'a>(
+        self,
+        position: Vec3,
+        candidates: impl Iterator<Item = &'a TankState>,
+        max_range: f32,
+    ) -> Option<&'a TankState> {
+        let distance = |tank: &TankState| (tank.position - position).xz().length();
+        let in_range = candidates.filter(|tank| distance(tank) <= max_range);
+
+        match self {
+            Self::Nearest => in_range.min_by(|a, b| distance(a).total_cmp(&distance(b))),
+            Self::Weakest => in_range.min_by(|a, b| {
+                a.health
+                    .total_cmp(&b.health)
+                    .then(distance(a).total_cmp(&distance(b)))
+            }),
+            Self::Threat => {
+                in_range.max_by(|a, b| threat(a, position).total_cmp(&threat(b, position)))
+            }
+        }
+    }
+}
+
+/// How dangerous a tank is to whoever is at `position`. Its health counts for more the closer it
+/// is, and up to double when it is facing `position`.
+pub fn threat(tank: &TankState, position: Vec3) -> f32 {
+    let offset = (position - tank.position).xz();
+    let facing = Vec2::new(tank.heading.sin(), tank.heading.cos())
+        .dot(offset.normalize_or_zero())
+        .max(0.0);
+
+    tank.health * (1.0 + facing) / offset.length().max(1.0)
+}
+
+/// The furthest a cannonball can fly across flat ground at this launch speed, under this
+/// downward gravity and without drag.
+pub fn max_range(launch_speed: f32, gravity: f32) -> f32 {
+    launch_speed * launch_speed / gravity
+}
@@ -0,0 +1,119 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::ScenarioConfig, tank::tank_color};
+
+/// How much of a tank's own color from [`tank_color`] shows through its team's color
+const TANK_SHADE: f32 = 0.3;
+
+/// The team a tank fights for. Tanks without one fight for themselves.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Team(pub u32);
+
+impl Team {
+    /// Whether two tanks are on the same team. Tanks without a team have no allies.
+    pub fn allied(a: Option<Team>, b: Option<Team>) -> bool {
+        a.is_some() && a == b
+    }
+
+    /// The color of the whole team, with the team hues spread evenly around the color wheel.
+    pub fn color(self, team_count: u32) -> Color {
+        Color::hsl(self.0 as f32 * 360.0 / team_count.max(1) as f32, 1.0, 0.5)
+    }
+
+    /// The color of one tank on this team: mostly the team color, shaded towards the tank's own.
+    pub fn tank_color(self, team_count: u32, tank_id: u32) -> Color {
+        let team = Vec4::from(self.color(team_count));
+        let tank = Vec4::from(tank_color(tank_id));
+
+        team.lerp(tank, TANK_SHADE).into()
+    }
+}
+
+/// What a cannonball does when it hits a tank on the shooter's team.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FriendlyFire {
+    /// Fly straight through teammates
+    Off,
+    /// Stop against teammates without damaging them
+    Blocked,
+    /// Damage teammates like anyone else
+    On,
+}
+
+impl FromStr for FriendlyFire {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "off" => Ok(Self::Off),
+            "blocked" => Ok(Self::Blocked),
+            "on" => Ok(Self::On),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The team of every tank the scenario spawns, from [`assign_teams`].
+#[derive(Resource)]
+pub struct TeamAssignments {
+    /// Indexed by tank id, with the player first
+    pub teams: Vec<Option<Team>>,
+    pub team_count: u32,
+}
+
+impl FromWorld for TeamAssignments {
+    fn from_world(world: &mut World) -> Self {
+        let scenario = world.get_resource_or_insert_with(ScenarioConfig::default);
+        let teams = assign_teams(&scenario);
+        let team_count = teams.iter().flatten().max().map_or(0, |team| team.0 + 1);
+
+        Self { teams, team_count }
+    }
+}
+
+impl TeamAssignments {
+    /// Number of tanks the scenario spawns, including the player.
+    pub fn tank_count(&self) -> u32 {
+        self.teams.len() as u32
+    }
+
+    pub fn team(&self, id: u32) -> Option<Team> {
+        self.teams.get(id as usize).copied().flatten()
+    }
+
+    /// A tank's color, tinted with its team's hue if it has one.
+    pub fn tank_color(&self, id: u32) -> Color {
+        match self.team(id) {
+            Some(team) => team.tank_color(self.team_count, id),
+            None => tank_color(id),
+        }
+    }
+}
+
+/// The team of every tank the scenario spawns, indexed by tank id, with the player first.
+///
+/// `team_sizes` sets the number of teams and the tanks in each, in place of `team_count` and
+/// `tank_count`. Otherwise `tank_count` tanks are split into `team_count` teams as evenly as
+/// possible, or all fight for themselves if `team_count` is zero.
+pub fn assign_teams(scenario: &ScenarioConfig) -> Vec<Option<Team>> {
+    if !scenario.team_sizes.is_empty() {
+        return (0..)
+            .zip(&scenario.team_sizes)
+            .flat_map(|(team, &size)| (0..size).map(move |_| Some(Team(team))))
+            .collect();
+    }
+
+    let tank_count = scenario.tank_count;
+
+    (0..tank_count)
+        .map(|id| {
+            // Keep teams together, so they start next to each other around the ring.
+            (scenario.team_count > 0)
+                .then(|| Team((id as u64 * scenario.team_count as u64 / tank_count as u64) as u32))
+        })
+        .collect()
+}
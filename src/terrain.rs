@@ -0,0 +1,200 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use noise::NoiseFn;
+
+use crate::{ai::Noise, config::ScenarioConfig};
+
+/// Where the terrain samples the 3D noise function, far away from the slices used by the AI tanks
+const TERRAIN_NOISE_SLICE: f64 = -1000.5;
+
+/// Generates the heightfield terrain from the noise function and spawns its mesh.
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Terrain>()
+            .add_systems(Startup, spawn_terrain);
+    }
+}
+
+/// A square heightfield centered on the origin, triangulated the same way for the mesh and for
+/// height queries, so everything rests exactly on the rendered surface.
+#[derive(Resource, Clone)]
+pub struct Terrain {
+    size: f32,
+    cell_size: f32,
+    /// Number of vertices along each side
+    vertices: usize,
+    /// Row-major heights, `x` varying fastest
+    heights: Vec<f32>,
+}
+
+impl FromWorld for Terrain {
+    fn from_world(world: &mut World) -> Self {
+        world.init_resource::<ScenarioConfig>();
+        world.init_resource::<Noise>();
+
+        let scenario = world.resource::<ScenarioConfig>();
+        let noise = world.resource::<Noise>();
+
+        Self::generate(
+            scenario.floor_size,
+            scenario.terrain_cell_size,
+            scenario.terrain_height,
+            scenario.terrain_feature_size,
+            |x, z| noise.generator.get([x, TERRAIN_NOISE_SLICE, z]) as f32,
+        )
+    }
+}
+
+impl Terrain {
+    /// Samples `noise` at every vertex, with `feature_size` world units per noise unit, and
+    /// scales it to `height`.
+    pub fn generate(
+        size: f32,
+        cell_size: f32,
+        height: f32,
+        feature_size: f32,
+        noise: impl Fn(f64, f64) -> f32,
+    ) -> Self {
+        let cells = (size / cell_size).ceil().max(1.0) as usize;
+        let vertices = cells + 1;
+        let cell_size = size / cells as f32;
+
+        let mut heights = Vec::with_capacity(vertices * vertices);
+
+        for j in 0..vertices {
+            for i in 0..vertices {
+                let x = (i as f32 * cell_size - size / 2.0) / feature_size;
+                let z = (j as f32 * cell_size - size / 2.0) / feature_size;
+                heights.push(noise(x as f64, z as f64) * height);
+            }
+        }
+
+        Self {
+            size,
+            cell_size,
+            vertices,
+            heights,
+        }
+    }
+
+    /// Height of the surface at a point. Outside the terrain the edge height continues outwards.
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let (corners, fx, fz) = self.locate(x, z);
+        let [a, b, c, d] = corners;
+
+        // Each cell is split along the diagonal from b to c.
+        if fx + fz <= 1.0 {
+            a + fx * (b - a) + fz * (c - a)
+        } else {
+            d + (1.0 - fx) * (c - d) + (1.0 - fz) * (b - d)
+        }
+    }
+
+    /// Normal of the triangle under a point.
+    pub fn normal_at(&self, x: f32, z: f32) -> Vec3 {
+        let (corners, fx, fz) = self.locate(x, z);
+        let [a, b, c, d] = corners;
+
+        let (slope_x, slope_z) = if fx + fz <= 1.0 {
+            (b - a, c - a)
+        } else {
+            (d - c, d - b)
+        };
+
+        Vec3::new(-slope_x, self.cell_size, -slope_z).normalize()
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// Builds a mesh of the terrain with smooth normals.
+    pub fn mesh(&self) -> Mesh {
+        let n = self.vertices;
+        let mut positions = Vec::with_capacity(n * n);
+        let mut normals = Vec::with_capacity(n * n);
+        let mut uvs = Vec::with_capacity(n * n);
+
+        for j in 0..n {
+            for i in 0..n {
+                let height = |i: usize, j: usize| self.heights[j.min(n - 1) * n + i.min(n - 1)];
+
+                let x = i as f32 * self.cell_size - self.size / 2.0;
+                let z = j as f32 * self.cell_size - self.size / 2.0;
+
+                // Central differences, one-sided at the edges.
+                let slope_x = height(i + 1, j) - height(i.saturating_sub(1), j);
+                let slope_z = height(i, j + 1) - height(i, j.saturating_sub(1));
+
+                positions.push([x, height(i, j), z]);
+                normals.push(
+                    Vec3::new(-slope_x, 2.0 * self.cell_size, -slope_z)
+                        .normalize()
+                        .to_array(),
+                );
+                uvs.push([i as f32 / (n - 1) as f32, j as f32 / (n - 1) as f32]);
+            }
+        }
+
+        let mut indices = Vec::with_capacity((n - 1) * (n - 1) * 6);
+
+        for j in 0..n - 1 {
+            for i in 0..n - 1 {
+                let a = (j * n + i) as u32;
+                let b = a + 1;
+                let c = a + n as u32;
+                let d = c + 1;
+
+                indices.extend_from_slice(&[a, c, b, b, c, d]);
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+
+    /// The heights at the corners of the cell containing a point, ordered `[(0, 0), (1, 0),
+    /// (0, 1), (1, 1)]`, and the position of the point within the cell.
+    fn locate(&self, x: f32, z: f32) -> ([f32; 4], f32, f32) {
+        let cells = (self.vertices - 1) as f32;
+
+        let gx = ((x + self.size / 2.0) / self.cell_size).clamp(0.0, cells);
+        let gz = ((z + self.size / 2.0) / self.cell_size).clamp(0.0, cells);
+
+        // Points on the far edges belong to the last cell.
+        let i = (gx.floor() as usize).min(self.vertices - 2);
+        let j = (gz.floor() as usize).min(self.vertices - 2);
+
+        let height = |i: usize, j: usize| self.heights[j * self.vertices + i];
+
+        let corners = [
+            height(i, j),
+            height(i + 1, j),
+            height(i, j + 1),
+            height(i + 1, j + 1),
+        ];
+
+        (corners, gx - i as f32, gz - j as f32)
+    }
+}
+
+fn spawn_terrain(
+    mut commands: Commands,
+    terrain: Res<Terrain>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(terrain.mesh()),
+        material: materials.add(Color::rgb(0.8, 0.8, 0.8).into()),
+        ..default()
+    });
+}
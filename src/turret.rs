@@ -0,0 +1,166 @@
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+    transform::TransformSystem,
+};
+
+/// Where the turret pivots on the hull, in the hull's local coordinates. The barrel pivots at the
+/// same point, so aiming never moves the pivot.
+pub const TURRET_OFFSET: Vec3 = Vec3::new(0.0, 1.035, 0.1);
+
+/// Distance from the barrel pivot to the tip of the cannon
+pub const BARREL_LENGTH: f32 = 0.3;
+
+/// The direction the cannon points at rest, in the hull's local coordinates, matching the
+/// `tank.glb` model. It isn't normalized; its length scales the muzzle velocity.
+pub const REST_BARREL_DIRECTION: Vec3 = Vec3::new(0.0, 0.717, 0.8);
+
+/// Poses the turret and barrel entities of every tank from their yaw and pitch.
+///
+/// `tank.glb` is a single mesh with the turret modelled at rest, so it stays on the hull; the
+/// turret and barrel entities are where a split model's meshes would go.
+pub struct TurretPlugin;
+
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (pose::<Turret>, pose::<Barrel>).before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// The turret of a tank, a child of the hull that turns about the hull's Y axis.
+#[derive(Component, Default)]
+pub struct Turret {
+    /// Angle relative to the hull's heading, in radians
+    pub yaw: f32,
+}
+
+/// The barrel of a tank, a child of the turret that tilts up and down.
+#[derive(Component)]
+pub struct Barrel {
+    /// Angle above the turret's horizon, in radians
+    pub pitch: f32,
+}
+
+impl Default for Barrel {
+    fn default() -> Self {
+        Self {
+            pitch: REST_BARREL_DIRECTION.y.atan2(REST_BARREL_DIRECTION.z),
+        }
+    }
+}
+
+/// The turret and barrel entities of a tank, kept on the hull.
+#[derive(Component, Clone, Copy)]
+pub struct TankParts {
+    pub turret: Entity,
+    pub barrel: Entity,
+}
+
+/// Spawns a turret with a barrel as children of a hull, and links them to it.
+pub fn spawn_turret(hull: &mut EntityCommands) {
+    let mut barrel = Entity::PLACEHOLDER;
+    let mut turret = Entity::PLACEHOLDER;
+
+    hull.with_children(|hull| {
+        turret = hull
+            .spawn((
+                SpatialBundle::from_transform(Turret::default().local_transform()),
+                Turret::default(),
+            ))
+            .with_children(|turret| {
+                barrel = turret
+                    .spawn((
+                        SpatialBundle::from_transform(Barrel::default().local_transform()),
+                        Barrel::default(),
+                    ))
+                    .id();
+            })
+            .id();
+    });
+
+    hull.insert(TankParts { turret, barrel });
+}
+
+/// Where a tank's turret pivots, in world coordinates. This doesn't depend on where it aims.
+pub fn turret_position(hull_transform: &Transform) -> Vec3 {
+    hull_transform.transform_point(TURRET_OFFSET)
+}
+
+/// The turrets and barrels of all tanks, for aiming and firing from simulation systems.
+#[derive(SystemParam)]
+pub struct Turrets<'w, 's> {
+    turrets: Query<'w, 's, &'static mut Turret>,
+    barrels: Query<'w, 's, &'static mut Barrel>,
+}
+
+impl Turrets<'_, '_> {
+    /// Turns the turret and tilts the barrel to point along `direction`, in world coordinates.
+    pub fn aim(&mut self, parts: &TankParts, hull_transform: &Transform, direction: Vec3) {
+        let local = hull_transform.rotation.inverse() * direction;
+
+        if let Ok(mut turret) = self.turrets.get_mut(parts.turret) {
+            turret.yaw = local.x.atan2(local.z);
+        }
+
+        if let Ok(mut barrel) = self.barrels.get_mut(parts.barrel) {
+            barrel.pitch = local.y.atan2(local.xz().length());
+        }
+    }
+
+    /// The barrel's `GlobalTransform`, with its +Z axis along the cannon.
+    ///
+    /// This is what transform propagation will compute at the end of the frame, but up to date
+    /// with the hull's movement this tick.
+    pub fn barrel_transform(
+        &self,
+        parts: &TankParts,
+        hull_transform: &Transform,
+    ) -> GlobalTransform {
+        let turret = self.turrets.get(parts.turret).map_or_else(
+            |_| Turret::default().local_transform(),
+            Turret::local_transform,
+        );
+        let barrel = self.barrels.get(parts.barrel).map_or_else(
+            |_| Barrel::default().local_transform(),
+            Barrel::local_transform,
+        );
+
+        GlobalTransform::from(*hull_transform) * turret * barrel
+    }
+}
+
+/// Where cannonballs leave the cannon, from the barrel's [`GlobalTransform`].
+pub fn muzzle_position(barrel_transform: &GlobalTransform) -> Vec3 {
+    barrel_transform.transform_point(Vec3::Z * BARREL_LENGTH)
+}
+
+/// The direction the cannon points in, from the barrel's [`GlobalTransform`].
+pub fn muzzle_direction(barrel_transform: &GlobalTransform) -> Vec3 {
+    barrel_transform.back()
+}
+
+/// A part whose [`Transform`] follows its angles.
+trait Pose: Component {
+    fn local_transform(&self) -> Transform;
+}
+
+impl Pose for Turret {
+    fn local_transform(&self) -> Transform {
+        Transform::from_translation(TURRET_OFFSET).with_rotation(Quat::from_rotation_y(self.yaw))
+    }
+}
+
+impl Pose for Barrel {
+    fn local_transform(&self) -> Transform {
+        Transform::from_rotation(Quat::from_rotation_x(-self.pitch))
+    }
+}
+
+fn pose<T: Pose>(mut query: Query<(&T, &mut Transform), Changed<T>>) {
+    for (part, mut transform) in &mut query {
+        *transform = part.local_transform();
+    }
+}
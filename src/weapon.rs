@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How fast a tank can fire. Set for all tanks with `weapon` in the scenario.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeaponStats {
+    /// Minimum seconds between shots. Zero fires every tick.
+    pub fire_interval: f32,
+    /// Shots before the tank has to reload. Zero means the magazine never runs out.
+    pub magazine_size: u32,
+    /// Seconds it takes to refill an empty magazine
+    pub reload_time: f32,
+}
+
+impl WeaponStats {
+    pub const STANDARD: Self = Self {
+        fire_interval: 0.5,
+        magazine_size: 5,
+        reload_time: 3.0,
+    };
+
+    /// The original behavior of firing on every tick without ever reloading. This maximizes the
+    /// number of cannonballs for stress testing.
+    pub const STRESS: Self = Self {
+        fire_interval: 0.0,
+        magazine_size: 0,
+        reload_time: 0.0,
+    };
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Self::STANDARD),
+            "stress" => Some(Self::STRESS),
+            _ => None,
+        }
+    }
+}
+
+impl Default for WeaponStats {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+#[derive(Component)]
+pub struct Weapon {
+    pub stats: WeaponStats,
+    /// Shots left in the magazine
+    pub rounds: u32,
+    /// Seconds until the next shot is allowed
+    pub cooldown: f32,
+    /// Seconds until the magazine is refilled, while reloading
+    pub reloading: Option<f32>,
+}
+
+impl Weapon {
+    pub fn new(stats: WeaponStats) -> Self {
+        Self {
+            stats,
+            rounds: stats.magazine_size,
+            cooldown: 0.0,
+            reloading: None,
+        }
+    }
+
+    /// Advances the cooldown and reload timers. Call this once per tick, whether or not the tank
+    /// fires.
+    pub fn tick(&mut self, delta: f32) {
+        self.cooldown = (self.cooldown - delta).max(0.0);
+
+        if let Some(remaining) = &mut self.reloading {
+            *remaining -= delta;
+
+            if *remaining <= 0.0 {
+                self.reloading = None;
+                self.rounds = self.stats.magazine_size;
+            }
+        }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.cooldown <= 0.0 && self.reloading.is_none()
+    }
+
+    /// Uses up a round if the weapon is ready, returning whether a shot should be fired.
+    pub fn try_fire(&mut self) -> bool {
+        if !self.ready() {
+            return false;
+        }
+
+        self.cooldown = self.stats.fire_interval;
+
+        if self.stats.magazine_size > 0 {
+            self.rounds -= 1;
+
+            if self.rounds == 0 {
+                self.reloading = Some(self.stats.reload_time);
+            }
+        }
+
+        true
+    }
+}